@@ -0,0 +1,1296 @@
+//! Tauri commands exposed to the webview, plus the serializable types they exchange.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::export::{self, MeshOptions};
+use crate::file::{self, MazeFile};
+use crate::generator::{
+  self, Algorithm, Bias, DifficultyGradient, GenerationLimits, LockConfig, MazeBuilder,
+  PortalStrategy, Progress, Symmetry, VerticalConfig,
+};
+use crate::maze::{
+  self, Axis, Coord, Direction, FairnessReport, LevelInfo, Lock, Mask, Maze, MazeStats, TaggedCell,
+  Topology, ValidationError, WrapMode, ALL_WALLS,
+};
+use crate::solver;
+
+/// Maze as sent over the Tauri bridge.
+///
+/// `walls` holds one mask per cell in `x + y * width + z * width * height` order. Bits are
+/// set while the wall stands: east `1`, west `2`, south `4`, north `8`, up `16`, down `32`.
+/// `mask`, when present, flags the active cells in the same order. Tags and weave crossings
+/// on cells that are missing or masked off are dropped, and so are locks touching them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MazeDto {
+  pub width: u32,
+  pub height: u32,
+  pub depth: u32,
+  pub walls: Vec<u8>,
+  pub seed: Option<u64>,
+  pub entrance: Coord,
+  pub exit: Coord,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mask: Option<Vec<bool>>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<TaggedCell>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub crossings: Vec<Coord>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub locks: Vec<Lock>,
+  /// Edges that join up with the opposite one, for generation and solving alike.
+  #[serde(default, skip_serializing_if = "WrapMode::is_none")]
+  pub wrap: WrapMode,
+}
+
+impl From<&Maze> for MazeDto {
+  fn from(maze: &Maze) -> Self {
+    MazeDto {
+      width: maze.width(),
+      height: maze.height(),
+      depth: maze.depth(),
+      walls: maze.wall_data().to_vec(),
+      seed: maze.seed(),
+      entrance: maze.entrance(),
+      exit: maze.exit(),
+      mask: maze.mask().map(|mask| mask.flags().to_vec()),
+      tags: maze.tagged_cells(),
+      crossings: maze.crossings(),
+      locks: maze.locks().to_vec(),
+      wrap: maze.wrap(),
+    }
+  }
+}
+
+impl TryFrom<MazeDto> for Maze {
+  type Error = String;
+
+  fn try_from(dto: MazeDto) -> Result<Self, Self::Error> {
+    dto.into_maze().map(|(maze, _dropped)| maze)
+  }
+}
+
+impl MazeDto {
+  /// The maze `self` describes, along with every tag, crossing and lock dropped for sitting
+  /// outside it.
+  fn into_maze(self) -> Result<(Maze, Vec<ValidationError>), String> {
+    let dims = (self.width, self.height, self.depth);
+    maze::validate_layout(dims, self.walls.len(), self.mask.as_ref().map(Vec::len))
+      .map_err(|errors| errors[0].to_string())?;
+    let mask = self
+      .mask
+      .and_then(|flags| Mask::from_flags(self.width, self.height, self.depth, flags));
+    let mut maze = Maze {
+      width: self.width,
+      height: self.height,
+      depth: self.depth,
+      walls: self.walls.into_iter().map(|w| w & ALL_WALLS).collect(),
+      seed: self.seed,
+      entrance: self.entrance,
+      exit: self.exit,
+      mask,
+      tags: HashMap::new(),
+      crossings: HashSet::new(),
+      locks: Vec::new(),
+      wrap: self.wrap,
+    };
+    let dropped = maze.restore_annotations(self.tags, self.crossings, self.locks);
+    Ok((maze, dropped))
+  }
+}
+
+/// How a maze returned by [`generate_maze`] came about.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationReport {
+  /// Wall-clock time spent carving, without parsing the request or serializing the maze.
+  pub elapsed_ms: u64,
+  /// Cells in the grid, masked ones included.
+  pub cells: u64,
+  /// The [`Algorithm::id`] it was carved with.
+  pub algorithm: String,
+  pub seed: u64,
+  /// Carved at the reduced size of [`generate_preview`] rather than the one requested.
+  #[serde(default)]
+  pub preview: bool,
+}
+
+/// A freshly generated maze; serializes as the [`MazeDto`] fields plus `report` and `file`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedMaze {
+  #[serde(flatten)]
+  pub maze: MazeDto,
+  pub report: GenerationReport,
+  /// The maze as a [`MazeFile`] document that records how it was carved, to save as is and
+  /// carve again with [`regenerate_maze`].
+  pub file: String,
+}
+
+/// What the settings screen needs to know about an [`Algorithm`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgorithmInfo {
+  /// The [`Algorithm::id`] to pass as `algorithm`.
+  pub id: &'static str,
+  pub name: &'static str,
+  pub supports_bias: bool,
+  pub perfect: bool,
+}
+
+impl From<Algorithm> for AlgorithmInfo {
+  fn from(algorithm: Algorithm) -> Self {
+    AlgorithmInfo {
+      id: algorithm.id(),
+      name: algorithm.name(),
+      supports_bias: algorithm.supports_bias(),
+      perfect: algorithm.is_perfect(),
+    }
+  }
+}
+
+/// Every algorithm [`generate_maze`] accepts, in [`Algorithm::ALL`] order.
+#[tauri::command]
+pub fn list_algorithms() -> Vec<AlgorithmInfo> {
+  Algorithm::ALL
+    .into_iter()
+    .map(AlgorithmInfo::from)
+    .collect()
+}
+
+/// Generates a maze of the requested size with the named algorithm (`recursive_backtracker`,
+/// `prim`, `kruskal`, `hunt_and_kill`, `aldous_broder` or `recursive_division`).
+///
+/// Passing the `seed` of a previous maze reproduces it exactly; without one a fresh seed is
+/// picked and reported back in [`MazeDto::seed`] and the [`GenerationReport`]. The other
+/// options are described on [`GenParams`].
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_maze(
+  width: u32,
+  height: u32,
+  depth: u32,
+  algorithm: String,
+  seed: Option<u64>,
+  vertical: Option<VerticalConfig>,
+  mask: Option<Vec<bool>>,
+  braiding: Option<f32>,
+  portals: Option<PortalStrategy>,
+  bias: Option<Bias>,
+  weave: Option<bool>,
+  topology: Option<String>,
+  symmetry: Option<Symmetry>,
+  locks: Option<LockConfig>,
+  gradient: Option<DifficultyGradient>,
+  wrap: Option<WrapMode>,
+) -> Result<GeneratedMaze, String> {
+  let params = GenParams {
+    width,
+    height,
+    depth,
+    algorithm,
+    vertical,
+    mask,
+    braiding,
+    portals,
+    bias,
+    weave,
+    topology,
+    symmetry,
+    locks,
+    gradient,
+    wrap,
+  };
+  params.generate(seed)
+}
+
+/// [`generate_maze`] for every one of `seeds`, results in the same order.
+///
+/// Each maze is generated on its own, so one failure does not affect the rest. With the
+/// `parallel` feature they are spread over all cores.
+#[tauri::command]
+pub fn generate_batch(params: GenParams, seeds: Vec<u64>) -> Vec<Result<GeneratedMaze, String>> {
+  let generate = |&seed: &u64| params.generate(Some(seed));
+  #[cfg(feature = "parallel")]
+  let results = {
+    use rayon::prelude::*;
+    seeds.par_iter().map(generate).collect()
+  };
+  #[cfg(not(feature = "parallel"))]
+  let results = seeds.iter().map(generate).collect();
+  results
+}
+
+/// A cheap stand-in for [`generate_maze`] with `params`, for thumbnails: the dimensions are
+/// scaled down so the largest is at most `max_dim`, and [`GenerationReport::preview`] is set.
+#[tauri::command]
+pub fn generate_preview(
+  params: GenParams,
+  max_dim: u32,
+  seed: Option<u64>,
+) -> Result<GeneratedMaze, String> {
+  let mut generated = params.preview(max_dim)?.generate(seed)?;
+  generated.report.preview = true;
+  Ok(generated)
+}
+
+/// Payload of the `maze-progress` events sent by [`generate_maze_progress`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+  pub carved: u64,
+  pub total: u64,
+  /// The finished maze, on the last event of a successful run.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub maze: Option<MazeDto>,
+  /// Why the run stopped, on the last event of a failed or cancelled run.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+/// Cancel flag of the background generation started last, if it is still running.
+#[derive(Default)]
+pub struct GenerationState {
+  running: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+}
+
+/// [`generate_maze`] on a worker thread, for mazes big enough to stall the UI.
+///
+/// Returns right away, failing only on arguments that do not parse. The worker emits
+/// `maze-progress` with a [`ProgressEvent`] every 1% of the carving, then a last event holding
+/// the maze or the error. Starting another run cancels the previous one.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_maze_progress(
+  window: tauri::Window,
+  state: tauri::State<'_, GenerationState>,
+  width: u32,
+  height: u32,
+  depth: u32,
+  algorithm: String,
+  seed: Option<u64>,
+  vertical: Option<VerticalConfig>,
+  mask: Option<Vec<bool>>,
+  braiding: Option<f32>,
+  portals: Option<PortalStrategy>,
+  bias: Option<Bias>,
+  weave: Option<bool>,
+  topology: Option<String>,
+  symmetry: Option<Symmetry>,
+  locks: Option<LockConfig>,
+  gradient: Option<DifficultyGradient>,
+  wrap: Option<WrapMode>,
+) -> Result<(), String> {
+  let params = GenParams {
+    width,
+    height,
+    depth,
+    algorithm,
+    vertical,
+    mask,
+    braiding,
+    portals,
+    bias,
+    weave,
+    topology,
+    symmetry,
+    locks,
+    gradient,
+    wrap,
+  };
+  let builder = params.builder(seed)?;
+  let cancel = Arc::new(AtomicBool::new(false));
+  let previous = state
+    .running
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .replace(Arc::clone(&cancel));
+  if let Some(previous) = previous {
+    previous.store(true, Ordering::Relaxed);
+  }
+
+  let running = Arc::clone(&state.running);
+  std::thread::spawn(move || {
+    let mut last = Progress {
+      carved: 0,
+      total: 0,
+    };
+    let result = builder.build_with_progress(&cancel, |progress| {
+      last = progress;
+      let _ = window.emit(
+        "maze-progress",
+        ProgressEvent {
+          carved: progress.carved,
+          total: progress.total,
+          maze: None,
+          error: None,
+        },
+      );
+    });
+    let (maze, error) = match result {
+      Ok(maze) => (Some(MazeDto::from(&maze)), None),
+      Err(e) => (None, Some(e.to_string())),
+    };
+    let _ = window.emit(
+      "maze-progress",
+      ProgressEvent {
+        carved: last.carved,
+        total: last.total,
+        maze,
+        error,
+      },
+    );
+    let mut running = running.lock().unwrap_or_else(|e| e.into_inner());
+    if running
+      .as_ref()
+      .is_some_and(|flag| Arc::ptr_eq(flag, &cancel))
+    {
+      *running = None;
+    }
+  });
+  Ok(())
+}
+
+/// Stops the run started by [`generate_maze_progress`]; its last event reports the
+/// cancellation. Does nothing when no run is in progress.
+#[tauri::command]
+pub fn cancel_generation(state: tauri::State<'_, GenerationState>) {
+  let running = state.running.lock().unwrap_or_else(|e| e.into_inner());
+  if let Some(cancel) = running.as_ref() {
+    cancel.store(true, Ordering::Relaxed);
+  }
+}
+
+/// Everything the generate commands take besides the seed.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenParams {
+  pub width: u32,
+  pub height: u32,
+  pub depth: u32,
+  /// An [`Algorithm`] id.
+  pub algorithm: String,
+  /// Carve each level separately and join them with these stairs.
+  #[serde(default)]
+  pub vertical: Option<VerticalConfig>,
+  /// Flags the cells to carve, in [`MazeDto::walls`] order; the rest stay solid.
+  #[serde(default)]
+  pub mask: Option<Vec<bool>>,
+  /// Fraction of the dead ends opened into loops.
+  #[serde(default)]
+  pub braiding: Option<f32>,
+  /// Where [`MazeDto::entrance`] and [`MazeDto::exit`] go.
+  #[serde(default)]
+  pub portals: Option<PortalStrategy>,
+  /// Stretches the backtracker's corridors along one axis.
+  #[serde(default)]
+  pub bias: Option<Bias>,
+  /// Lets the backtracker tunnel under corridors into [`MazeDto::crossings`].
+  #[serde(default)]
+  pub weave: Option<bool>,
+  /// Faces that may be carved: `"orthogonal6"` (the default) or `"orthogonal4_per_level"`.
+  #[serde(default)]
+  pub topology: Option<String>,
+  /// Mirror the carving across the grid; the mask, if any, must be symmetric already.
+  #[serde(default)]
+  pub symmetry: Option<Symmetry>,
+  /// Lock doors on the way to the exit, into [`MazeDto::locks`]; see [`solve_with_keys`].
+  #[serde(default)]
+  pub locks: Option<LockConfig>,
+  /// Braid more near the entrance than near the exit, or the reverse, on top of `braiding`.
+  #[serde(default)]
+  pub gradient: Option<DifficultyGradient>,
+  /// Join opposite edges into a torus, as recorded in [`MazeDto::wrap`].
+  #[serde(default)]
+  pub wrap: Option<WrapMode>,
+}
+
+impl GenParams {
+  /// Parses the parameters into a [`MazeBuilder`], seeded with `seed` or a fresh one.
+  fn builder(&self, seed: Option<u64>) -> Result<MazeBuilder, String> {
+    let (width, height, depth) = (self.width, self.height, self.depth);
+    let limits = limits();
+    // Before the mask, whose length check multiplies the dimensions too.
+    limits
+      .check((width, height, depth))
+      .map_err(|e| e.to_string())?;
+    let algorithm: Algorithm = self
+      .algorithm
+      .parse()
+      .map_err(|e: generator::GenerateError| e.to_string())?;
+    let mut builder = MazeBuilder::new()
+      .dimensions(width, height, depth)
+      .algorithm(algorithm)
+      .braiding(self.braiding.unwrap_or(0.0))
+      .portals(self.portals.unwrap_or_default())
+      .bias(self.bias.unwrap_or_default())
+      .weave(self.weave.unwrap_or(false))
+      .wrap(self.wrap.unwrap_or_default())
+      .limits(limits)
+      .topology(parse_topology(self.topology.as_deref())?);
+    if let Some(seed) = seed {
+      builder = builder.seed(seed);
+    }
+    if let Some(vertical) = self.vertical {
+      builder = builder.vertical(vertical);
+    }
+    if let Some(symmetry) = self.symmetry {
+      builder = builder.symmetry(symmetry);
+    }
+    if let Some(locks) = self.locks {
+      builder = builder.locks(locks);
+    }
+    if let Some(gradient) = self.gradient {
+      builder = builder.gradient(gradient);
+    }
+    if let Some(flags) = self.mask.clone() {
+      let found = flags.len();
+      let mask = Mask::from_flags(width, height, depth, flags)
+        .ok_or_else(|| format!("mask carries {found} flags for a {width}x{height}x{depth} maze"))?;
+      builder = builder.mask(mask);
+    }
+    Ok(builder)
+  }
+
+  fn generate(&self, seed: Option<u64>) -> Result<GeneratedMaze, String> {
+    let builder = self.builder(seed)?;
+    let config = builder.config().map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let maze = Maze::generate_in(&config, builder.current_topology()).map_err(|e| e.to_string())?;
+    let elapsed = started.elapsed();
+    let file = MazeFile::generated(&maze, &config, builder.current_topology());
+    Ok(GeneratedMaze {
+      maze: MazeDto::from(&maze),
+      report: GenerationReport {
+        elapsed_ms: elapsed.as_millis() as u64,
+        cells: maze.cell_count() as u64,
+        // Parsing only accepts exact ids.
+        algorithm: self.algorithm.clone(),
+        seed: builder.current_seed(),
+        preview: false,
+      },
+      file: serde_json::to_string(&file).expect("maze files always serialize"),
+    })
+  }
+
+  /// The same request shrunk so no axis exceeds `max_dim`, keeping the proportions. The mask
+  /// and explicit portals are sampled down to match.
+  fn preview(&self, max_dim: u32) -> Result<GenParams, String> {
+    if max_dim == 0 {
+      return Err("max_dim must be at least 1".to_owned());
+    }
+    let full = [self.width, self.height, self.depth];
+    let largest = full.into_iter().max().unwrap_or(0);
+    if largest <= max_dim {
+      return Ok(self.clone());
+    }
+    // Rounded to the nearest cell, never below one.
+    let small = full.map(|v| {
+      let scaled =
+        (2 * u64::from(v) * u64::from(max_dim) + u64::from(largest)) / (2 * u64::from(largest));
+      scaled.max(1) as u32
+    });
+    let sample = |cell: Coord| -> Coord {
+      [0, 1, 2]
+        .map(|axis| (u64::from(cell[axis]) * u64::from(full[axis]) / u64::from(small[axis])) as u32)
+    };
+    let mask = match &self.mask {
+      None => None,
+      Some(flags) => {
+        let [width, height, depth] = full;
+        let expected = u64::from(width) * u64::from(height) * u64::from(depth);
+        if flags.len() as u64 != expected {
+          return Err(format!(
+            "mask carries {} flags for a {width}x{height}x{depth} maze",
+            flags.len()
+          ));
+        }
+        let index = |[x, y, z]: Coord| {
+          x as usize + (y as usize + z as usize * height as usize) * width as usize
+        };
+        let [w, h, d] = small;
+        let cells = (0..d).flat_map(|z| (0..h).flat_map(move |y| (0..w).map(move |x| [x, y, z])));
+        Some(cells.map(|cell| flags[index(sample(cell))]).collect())
+      }
+    };
+    let shrink = |cell: Coord| -> Coord {
+      [0, 1, 2]
+        .map(|axis| (u64::from(cell[axis]) * u64::from(small[axis]) / u64::from(full[axis])) as u32)
+    };
+    let portals = match self.portals {
+      Some(PortalStrategy::Explicit([entrance, exit])) => {
+        Some(PortalStrategy::Explicit([shrink(entrance), shrink(exit)]))
+      }
+      portals => portals,
+    };
+    Ok(GenParams {
+      width: small[0],
+      height: small[1],
+      depth: small[2],
+      mask,
+      portals,
+      ..self.clone()
+    })
+  }
+}
+
+/// Limits for the generate commands: [`generator::DEFAULT_MAX_CELLS`], or the cell count in
+/// `P3DM_MAX_CELLS` when it is set to a number.
+fn limits() -> GenerationLimits {
+  std::env::var("P3DM_MAX_CELLS")
+    .ok()
+    .and_then(|value| value.trim().parse().ok())
+    .map_or_else(GenerationLimits::default, |max_cells| GenerationLimits {
+      max_cells,
+    })
+}
+
+/// The topology named by `id`, [`maze::Orthogonal6`] when absent.
+fn parse_topology(id: Option<&str>) -> Result<&'static dyn Topology, String> {
+  match id {
+    None => Ok(&maze::Orthogonal6),
+    Some(id) => maze::topology(id).ok_or_else(|| format!("unknown topology {id:?}")),
+  }
+}
+
+/// Shortest path from `start` to `end` (both included), following stairs between levels
+/// unless `topology` keeps to one level; see [`generate_maze`].
+#[tauri::command]
+pub fn solve_maze(
+  maze: MazeDto,
+  start: Coord,
+  end: Coord,
+  topology: Option<String>,
+) -> Result<Vec<Coord>, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  solver::bfs_in(&maze, start, end, topology).map_err(|e| e.to_string())
+}
+
+/// [`solve_maze`] that also returns every cell the search reached, so the frontend can show
+/// how far it got when there is no path.
+#[tauri::command]
+pub fn explore_maze(
+  maze: MazeDto,
+  start: Coord,
+  end: Coord,
+  topology: Option<String>,
+) -> Result<solver::SolveResult, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  solver::explore_in(&maze, start, end, topology).map_err(|e| e.to_string())
+}
+
+/// [`solve_maze`] that may only pass each of the maze's locked doors after visiting its key;
+/// see [`solver::solve_with_keys`].
+#[tauri::command]
+pub fn solve_with_keys(
+  maze: MazeDto,
+  start: Coord,
+  end: Coord,
+  topology: Option<String>,
+) -> Result<Vec<Coord>, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  solver::solve_with_keys_in(&maze, start, end, topology).map_err(|e| e.to_string())
+}
+
+/// The search behind [`explore_maze`] as one [`solver::SolverStep`] per distance from `start`,
+/// for playing it back frame by frame.
+#[tauri::command]
+pub fn solve_steps(
+  maze: MazeDto,
+  start: Coord,
+  end: Coord,
+  topology: Option<String>,
+) -> Result<Vec<solver::SolverStep>, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  let steps = solver::SolverIter::new(&maze, start, end, topology).map_err(|e| e.to_string())?;
+  Ok(steps.collect())
+}
+
+/// Distances from one cell for heatmap colouring.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistanceField {
+  /// Moves from the source to each cell in [`MazeDto::walls`] order, `u32::MAX` where
+  /// unreachable.
+  pub distances: Vec<u32>,
+  /// Largest reachable distance, for normalizing colours.
+  pub max: u32,
+}
+
+/// Shortest-path distance from `source` (the entrance by default) to every cell, moving as
+/// `topology` allows.
+#[tauri::command]
+pub fn distance_field(
+  maze: MazeDto,
+  source: Option<Coord>,
+  topology: Option<String>,
+) -> Result<DistanceField, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  let source = source.unwrap_or(maze.entrance());
+  if !maze.contains(source) {
+    return Err(solver::SolveError::OutOfBounds(source).to_string());
+  }
+  let distances = solver::distance_field_in(&maze, source, topology);
+  let max = distances
+    .iter()
+    .copied()
+    .filter(|&d| d != u32::MAX)
+    .max()
+    .unwrap_or(0);
+  Ok(DistanceField { distances, max })
+}
+
+/// Binary glTF (`.glb`) bytes for `maze`, returned as a raw IPC payload.
+#[tauri::command]
+pub fn export_gltf(
+  maze: MazeDto,
+  options: Option<MeshOptions>,
+) -> Result<tauri::ipc::Response, String> {
+  let maze = Maze::try_from(maze)?;
+  let bytes = export::to_gltf(&maze, &options.unwrap_or_default()).map_err(|e| e.to_string())?;
+  Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Wavefront OBJ text for `maze`, ready to save as a `.obj` file.
+#[tauri::command]
+pub fn export_obj(maze: MazeDto, options: Option<MeshOptions>) -> Result<String, String> {
+  let maze = Maze::try_from(maze)?;
+  Ok(export::to_obj(&maze, &options.unwrap_or_default()))
+}
+
+/// The [`solve_maze`] path from `start` to `end`, by default the entrance and exit, as CSV;
+/// see [`export::path_to_csv`].
+#[tauri::command]
+pub fn export_path_csv(
+  maze: MazeDto,
+  start: Option<Coord>,
+  end: Option<Coord>,
+  topology: Option<String>,
+) -> Result<String, String> {
+  let maze = Maze::try_from(maze)?;
+  let topology = parse_topology(topology.as_deref())?;
+  let start = start.unwrap_or(maze.entrance());
+  let end = end.unwrap_or(maze.exit());
+  let path = solver::bfs_in(&maze, start, end, topology).map_err(|e| e.to_string())?;
+  Ok(export::path_to_csv(&path))
+}
+
+/// `maze` with the box `min..=max` re-carved from `seed`; cells outside the box keep their
+/// walls.
+#[tauri::command]
+pub fn regenerate_region(
+  maze: MazeDto,
+  min: Coord,
+  max: Coord,
+  seed: Option<u64>,
+) -> Result<MazeDto, String> {
+  let mut maze = Maze::try_from(maze)?;
+  maze
+    .regenerate_region(min, max, seed.unwrap_or_else(generator::fresh_seed))
+    .map_err(|e| e.to_string())?;
+  Ok(MazeDto::from(&maze))
+}
+
+/// `maze` with dead ends walled off `passes` times over, for a speed-run layout; see
+/// [`maze::cull_dead_ends`].
+#[tauri::command]
+pub fn cull_dead_ends(maze: MazeDto, passes: u32) -> Result<MazeDto, String> {
+  let mut maze = Maze::try_from(maze)?;
+  maze::cull_dead_ends(&mut maze, passes);
+  Ok(MazeDto::from(&maze))
+}
+
+/// `first` and `second` glued together along `axis`, with a passage across the seam; see
+/// [`Maze::join`].
+#[tauri::command]
+pub fn join_mazes(first: MazeDto, second: MazeDto, axis: Axis) -> Result<MazeDto, String> {
+  let first = Maze::try_from(first)?;
+  let second = Maze::try_from(second)?;
+  let joined = first.join(&second, axis).map_err(|e| e.to_string())?;
+  Ok(MazeDto::from(&joined))
+}
+
+/// [`Maze::content_hash`] of `maze` as 16 hex digits, since a `u64` does not survive the trip
+/// through a JS number.
+#[tauri::command]
+pub fn maze_hash(maze: MazeDto) -> Result<String, String> {
+  let maze = Maze::try_from(maze)?;
+  Ok(format!("{:016x}", maze.content_hash()))
+}
+
+/// Cell of a [`GraphDto`], or one corridor of a weave crossing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+  /// Position of the cell in [`MazeDto::walls`].
+  pub cell: usize,
+  pub coord: Coord,
+}
+
+/// Connectivity of a maze as a plain undirected graph, for analysis outside the app.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphDto {
+  /// Every active cell, in [`MazeDto::walls`] order, then a second node for each weave
+  /// crossing in [`MazeDto::crossings`] order. A crossing's first node stands for its
+  /// east-west corridor and the second for the north-south one.
+  pub nodes: Vec<GraphNode>,
+  /// One pair of positions in `nodes` per open passage, lower position first.
+  pub edges: Vec<[usize; 2]>,
+}
+
+/// The cells of `maze` and the passages between them, leaving out masked cells.
+///
+/// The corridors over and under a weave crossing get a node each, as in the solver, so they
+/// never meet; passages through the edges [`MazeDto::wrap`] joins are included.
+#[tauri::command]
+pub fn maze_graph(maze: MazeDto) -> Result<GraphDto, String> {
+  let maze = Maze::try_from(maze)?;
+  let mut nodes: Vec<GraphNode> = (0..maze.cell_count())
+    .map(|cell| GraphNode {
+      cell,
+      coord: maze.coord(cell),
+    })
+    .filter(|node| maze.is_active(node.coord))
+    .collect();
+  let mut position = vec![usize::MAX; maze.cell_count()];
+  for (at, node) in nodes.iter().enumerate() {
+    position[node.cell] = at;
+  }
+  let mut north_south = HashMap::new();
+  for coord in maze.crossings() {
+    let cell = maze.index(coord);
+    north_south.insert(cell, nodes.len());
+    nodes.push(GraphNode { cell, coord });
+  }
+  // The node a passage along `dir` uses at `cell`; crossings cannot be passed vertically.
+  let node = |cell: usize, dir: Direction| match (north_south.get(&cell), dir) {
+    (None, _) | (Some(_), Direction::East | Direction::West) => Some(position[cell]),
+    (Some(&node), Direction::South | Direction::North) => Some(node),
+    (Some(_), Direction::Up | Direction::Down) => None,
+  };
+  // Each passage is looked at once, from the cell west, north or below it.
+  let forward = [Direction::East, Direction::South, Direction::Up];
+  let mut edges = Vec::new();
+  for index in 0..maze.cell_count() {
+    let coord = maze.coord(index);
+    for (dir, next) in maze.open_neighbors(coord) {
+      if !forward.contains(&dir) {
+        continue;
+      }
+      if let (Some(a), Some(b)) = (node(index, dir), node(maze.index(next), dir)) {
+        edges.push([a.min(b), a.max(b)]);
+      }
+    }
+  }
+  Ok(GraphDto { nodes, edges })
+}
+
+/// Every consistency problem in `maze`, or an empty list if it is sound.
+///
+/// Meant for hand-edited files, which may carry one-sided walls or a grid that does not match
+/// the declared size; see [`maze::validate`]. Tags, crossings and locks that loading would
+/// drop are reported too.
+#[tauri::command]
+pub fn validate_maze(maze: MazeDto) -> Vec<ValidationError> {
+  let dims = (maze.width, maze.height, maze.depth);
+  if let Err(errors) =
+    maze::validate_layout(dims, maze.walls.len(), maze.mask.as_ref().map(Vec::len))
+  {
+    return errors;
+  }
+  let (maze, mut errors) = maze.into_maze().expect("layout was validated");
+  errors.extend(maze::validate(&maze).err().unwrap_or_default());
+  errors
+}
+
+/// Parses a saved maze file, checks it with [`maze::validate`] and solves it from `start` to
+/// `end`, for scripts that want one round trip.
+///
+/// Errors start with `parse:` when the document cannot be read as a maze file, `invalid:`
+/// when the maze it describes is inconsistent or carries tags, crossings or locks outside it,
+/// `out of bounds:` when `start` or `end` is not an active cell, and `unsolvable:` when they
+/// are not connected.
+#[tauri::command]
+pub fn load_and_solve(json: String, start: Coord, end: Coord) -> Result<Vec<Coord>, String> {
+  let (maze, mut problems) =
+    Maze::from_json_with_warnings(&json).map_err(|e| format!("parse: {e}"))?;
+  problems.extend(maze::validate(&maze).err().unwrap_or_default());
+  if !problems.is_empty() {
+    let reasons: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    return Err(format!("invalid: {}", reasons.join("; ")));
+  }
+  solver::bfs(&maze, start, end).map_err(|e| match e {
+    solver::SolveError::OutOfBounds(_) => format!("out of bounds: {e}"),
+    _ => format!("unsolvable: {e}"),
+  })
+}
+
+/// Carves the maze a saved [`GeneratedMaze::file`] describes again from its seed and recipe.
+///
+/// Fails rather than returning a different maze when the file lacks a recipe, this build
+/// cannot follow it, or it no longer carves the walls the file holds; tags outside the maze
+/// are reported the way [`load_and_solve`] does.
+#[tauri::command]
+pub fn regenerate_maze(json: String) -> Result<MazeDto, String> {
+  let file = MazeFile::from_json(&json).map_err(|e| e.to_string())?;
+  let (maze, dropped) = file::regenerate_from_file(&file).map_err(|e| e.to_string())?;
+  if !dropped.is_empty() {
+    let reasons: Vec<String> = dropped.iter().map(ToString::to_string).collect();
+    return Err(format!("invalid: {}", reasons.join("; ")));
+  }
+  Ok(MazeDto::from(&maze))
+}
+
+/// Top-down PNG of one level, `scale` pixels per cell, returned as a raw IPC payload.
+///
+/// `markers` highlights the entrance and exit.
+#[tauri::command]
+pub fn export_png(
+  maze: MazeDto,
+  level: u32,
+  scale: u32,
+  markers: Option<bool>,
+) -> Result<tauri::ipc::Response, String> {
+  let maze = Maze::try_from(maze)?;
+  let bytes = if markers.unwrap_or(false) {
+    export::to_png_with_markers(&maze, level, scale)
+  } else {
+    export::to_png(&maze, level, scale)
+  }
+  .map_err(|e| e.to_string())?;
+  Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Difficulty metrics for the level-select screen.
+#[tauri::command]
+pub fn maze_stats(maze: MazeDto) -> Result<MazeStats, String> {
+  let maze = Maze::try_from(maze)?;
+  Ok(maze::stats(&maze))
+}
+
+/// Stairs up and down on every level, and whether the entrance reaches it.
+#[tauri::command]
+pub fn level_connectivity(maze: MazeDto) -> Result<Vec<LevelInfo>, String> {
+  let maze = Maze::try_from(maze)?;
+  Ok(maze::level_connectivity(&maze))
+}
+
+/// Shortest path length from each of `starts` to `goal`, and how far apart they are.
+#[tauri::command]
+pub fn fairness_report(
+  maze: MazeDto,
+  starts: Vec<Coord>,
+  goal: Coord,
+) -> Result<FairnessReport, String> {
+  let maze = Maze::try_from(maze)?;
+  Ok(maze::fairness_report(&maze, &starts, goal))
+}
+
+/// Linux rendering workarounds in effect, for the frontend's diagnostics panel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RendererInfo {
+  /// WebKit's DMABUF renderer is off; the app turns it off unless the variable is already set.
+  pub dmabuf_disabled: bool,
+  /// OpenGL renders in software, through `P3DM_SOFTWARE_GL` or `LIBGL_ALWAYS_SOFTWARE`.
+  pub software_gl: bool,
+  /// WebKit's compositing mode is off, through `P3DM_WEBKIT_NO_COMPOSITING` or
+  /// `WEBKIT_DISABLE_COMPOSITING_MODE`.
+  pub no_compositing: bool,
+  /// [`std::env::consts::OS`], such as `"linux"` or `"windows"`.
+  pub os: String,
+}
+
+impl RendererInfo {
+  /// Whether the toggle variable `name` is on in the environment, by [`RendererInfo::is_on`].
+  pub fn toggle_on(name: &str) -> bool {
+    let value = std::env::var_os(name).map(|value| value.to_string_lossy().into_owned());
+    RendererInfo::is_on(value.as_deref())
+  }
+
+  /// Whether a toggle holding `value` is on: set, and neither empty nor `0`.
+  pub fn is_on(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+  }
+
+  /// Reads the toggles from the environment. They only exist for WebKitGTK, so every one is
+  /// `false` off Linux.
+  pub fn from_env() -> RendererInfo {
+    RendererInfo::from_vars(std::env::consts::OS, |name| {
+      std::env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+    })
+  }
+
+  /// [`RendererInfo::from_env`] on `os`, with `var` looking up each variable. A variable that
+  /// is empty or `0` counts as unset.
+  pub fn from_vars(os: &str, var: impl Fn(&str) -> Option<String>) -> RendererInfo {
+    let on = |name: &str| RendererInfo::is_on(var(name).as_deref());
+    let set = |names: &[&str]| os == "linux" && names.iter().any(|&name| on(name));
+    RendererInfo {
+      dmabuf_disabled: set(&["WEBKIT_DISABLE_DMABUF_RENDERER"]),
+      software_gl: set(&["P3DM_SOFTWARE_GL", "LIBGL_ALWAYS_SOFTWARE"]),
+      no_compositing: set(&[
+        "P3DM_WEBKIT_NO_COMPOSITING",
+        "WEBKIT_DISABLE_COMPOSITING_MODE",
+      ]),
+      os: os.to_owned(),
+    }
+  }
+}
+
+/// One line for bug reports, such as `WebKit toggles on linux: DMABUF renderer disabled`.
+impl fmt::Display for RendererInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let active: Vec<&str> = [
+      (self.dmabuf_disabled, "DMABUF renderer disabled"),
+      (self.no_compositing, "compositing disabled"),
+      (self.software_gl, "software OpenGL"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect();
+    let active = if active.is_empty() {
+      "none".to_owned()
+    } else {
+      active.join(", ")
+    };
+    write!(f, "WebKit toggles on {}: {active}", self.os)
+  }
+}
+
+/// Which of the compatibility toggles from `DESKTOP_BUILD.md` are active, to help explain a
+/// blank or black view.
+#[tauri::command]
+pub fn renderer_diagnostics() -> RendererInfo {
+  RendererInfo::from_env()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::maze::CellTag;
+
+  fn generate(width: u32, height: u32, depth: u32, algorithm: &str) -> Result<MazeDto, String> {
+    generate_maze(
+      width,
+      height,
+      depth,
+      algorithm.to_owned(),
+      Some(7),
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+      None,
+    )
+    .map(|generated| generated.maze)
+  }
+
+  fn passages(dto: &MazeDto) -> usize {
+    let open: u32 = dto
+      .walls
+      .iter()
+      .map(|w| (ALL_WALLS & !w).count_ones())
+      .sum();
+    open as usize / 2
+  }
+
+  #[test]
+  fn generates_a_perfect_maze_with_every_algorithm() {
+    for algorithm in Algorithm::ALL {
+      let dto = generate(7, 5, 3, algorithm.id()).unwrap();
+      assert_eq!(dto.walls.len(), 7 * 5 * 3);
+      assert_eq!(passages(&dto), 7 * 5 * 3 - 1, "{algorithm:?}");
+    }
+  }
+
+  #[test]
+  fn rejects_an_unknown_algorithm() {
+    let e = generate(4, 4, 1, "nope").unwrap_err();
+    assert!(e.contains("unknown algorithm 'nope'"), "{e}");
+  }
+
+  #[test]
+  fn rejects_a_zero_dimension() {
+    let e = generate(7, 0, 3, "prim").unwrap_err();
+    assert!(e.contains("invalid maze dimensions"), "{e}");
+  }
+
+  #[test]
+  fn validation_reports_dropped_tags() {
+    let mut dto = generate(4, 4, 1, "prim").unwrap();
+    dto.tags.push(TaggedCell {
+      cell: [0, 9, 0],
+      tag: CellTag::Treasure,
+    });
+    assert_eq!(
+      validate_maze(dto.clone()),
+      vec![ValidationError::DroppedTag { cell: [0, 9, 0] }]
+    );
+    assert!(Maze::try_from(dto).unwrap().tagged_cells().is_empty());
+  }
+
+  #[test]
+  fn a_generated_file_regenerates_the_same_maze() {
+    let params: GenParams = serde_json::from_str(
+      r#"{"width":7,"height":6,"depth":2,"algorithm":"kruskal","braiding":0.3,"weave":true}"#,
+    )
+    .unwrap();
+    let generated = params.generate(Some(8)).unwrap();
+    assert!(generated.file.contains(r#""rng":"chacha8""#));
+    assert_eq!(regenerate_maze(generated.file).unwrap(), generated.maze);
+  }
+
+  #[test]
+  fn a_batch_matches_generating_each_seed() {
+    let params: GenParams =
+      serde_json::from_str(r#"{"width":9,"height":7,"depth":2,"algorithm":"kruskal"}"#).unwrap();
+    let seeds = vec![3, 14, 159];
+    let batch = generate_batch(params, seeds.clone());
+    assert_eq!(batch.len(), 3);
+    for (generated, seed) in batch.into_iter().zip(seeds) {
+      let generated = generated.unwrap();
+      let maze = Maze::generate_with_seed((9, 7, 2), seed, Algorithm::Kruskal).unwrap();
+      assert_eq!(generated.maze, MazeDto::from(&maze), "seed {seed}");
+      assert_eq!(generated.report.seed, seed);
+    }
+  }
+
+  fn renderer(os: &str, vars: &[(&str, &str)]) -> RendererInfo {
+    RendererInfo::from_vars(os, |name| {
+      vars
+        .iter()
+        .find(|&&(set, _)| set == name)
+        .map(|&(_, value)| value.to_owned())
+    })
+  }
+
+  #[test]
+  fn reads_renderer_toggles() {
+    let info = renderer("linux", &[("P3DM_SOFTWARE_GL", "1")]);
+    assert!(info.software_gl);
+    assert!(!info.dmabuf_disabled && !info.no_compositing);
+    assert!(!renderer("windows", &[("P3DM_SOFTWARE_GL", "1")]).software_gl);
+  }
+
+  #[test]
+  fn empty_or_zero_toggles_are_off() {
+    let info = renderer(
+      "linux",
+      &[
+        ("P3DM_SOFTWARE_GL", "0"),
+        ("WEBKIT_DISABLE_DMABUF_RENDERER", ""),
+        ("WEBKIT_DISABLE_COMPOSITING_MODE", "yes"),
+      ],
+    );
+    assert!(!info.software_gl && !info.dmabuf_disabled);
+    assert!(info.no_compositing);
+  }
+
+  #[test]
+  fn the_report_echoes_the_request() {
+    let params: GenParams =
+      serde_json::from_str(r#"{"width":6,"height":5,"depth":3,"algorithm":"hunt_and_kill"}"#)
+        .unwrap();
+    let seeded = params.generate(Some(42)).unwrap().report;
+    assert_eq!(seeded.cells, 6 * 5 * 3);
+    assert_eq!(seeded.algorithm, "hunt_and_kill");
+    assert_eq!(seeded.seed, 42);
+    assert!(!seeded.preview);
+
+    let fresh = params.generate(None).unwrap();
+    assert_eq!(fresh.maze.seed, Some(fresh.report.seed));
+  }
+
+  #[test]
+  fn loads_and_solves_a_saved_maze() {
+    let maze = Maze::generate_with_seed((5, 5, 2), 8, Algorithm::Kruskal).unwrap();
+    let path = load_and_solve(maze.to_json(), [0, 0, 0], [4, 4, 1]).unwrap();
+    assert_eq!(path, solver::bfs(&maze, [0, 0, 0], [4, 4, 1]).unwrap());
+  }
+
+  #[test]
+  fn load_and_solve_prefixes_its_errors() {
+    let e = load_and_solve("{not json".to_owned(), [0, 0, 0], [1, 0, 0]).unwrap_err();
+    assert!(e.starts_with("parse: "), "{e}");
+
+    let maze = Maze::generate_with_seed((3, 3, 1), 8, Algorithm::Prim).unwrap();
+    let e = load_and_solve(maze.to_json(), [0, 0, 0], [7, 0, 0]).unwrap_err();
+    assert!(e.starts_with("out of bounds: "), "{e}");
+
+    // Two corridors that pass validation but never meet.
+    let mut split = Maze::new(4, 1, 1);
+    split.carve([0, 0, 0], Direction::East);
+    split.carve([2, 0, 0], Direction::East);
+    assert_eq!(maze::validate(&split), Ok(()));
+    let e = load_and_solve(split.to_json(), [0, 0, 0], [3, 0, 0]).unwrap_err();
+    assert!(e.starts_with("unsolvable: "), "{e}");
+
+    let mut json: serde_json::Value = serde_json::from_str(&maze.to_json()).unwrap();
+    json["walls"][0] = 0.into();
+    let e = load_and_solve(json.to_string(), [0, 0, 0], [2, 2, 0]).unwrap_err();
+    assert!(e.starts_with("invalid: "), "{e}");
+  }
+
+  #[test]
+  fn previews_shrink_to_the_largest_axis() {
+    let params = |json: &str| -> GenParams { serde_json::from_str(json).unwrap() };
+    let preview = generate_preview(
+      params(r#"{"width":400,"height":400,"depth":10,"algorithm":"prim"}"#),
+      40,
+      Some(1),
+    )
+    .unwrap();
+    let dims = (preview.maze.width, preview.maze.height, preview.maze.depth);
+    assert_eq!(dims, (40, 40, 1));
+    assert!(preview.report.preview);
+    assert_eq!(preview.report.cells, 40 * 40);
+
+    let wide = params(r#"{"width":400,"height":200,"depth":30,"algorithm":"prim"}"#);
+    let small = wide.preview(40).unwrap();
+    assert_eq!((small.width, small.height, small.depth), (40, 20, 3));
+    let tiny = params(r#"{"width":8,"height":6,"depth":2,"algorithm":"prim"}"#);
+    assert_eq!(tiny.preview(40).unwrap(), tiny);
+  }
+
+  #[test]
+  fn lists_every_algorithm_once() {
+    let listed = list_algorithms();
+    assert_eq!(listed.len(), Algorithm::ALL.len());
+    for algorithm in Algorithm::ALL {
+      let matching: Vec<&AlgorithmInfo> = listed
+        .iter()
+        .filter(|info| info.id == algorithm.id())
+        .collect();
+      assert_eq!(matching, [&AlgorithmInfo::from(algorithm)]);
+      assert_eq!(matching[0].id.parse::<Algorithm>(), Ok(algorithm));
+    }
+    let biased: Vec<&str> = listed
+      .iter()
+      .filter(|info| info.supports_bias)
+      .map(|info| info.id)
+      .collect();
+    assert_eq!(biased, ["recursive_backtracker"]);
+  }
+
+  #[test]
+  fn renderer_info_reads_as_one_line() {
+    assert_eq!(
+      renderer("linux", &[]).to_string(),
+      "WebKit toggles on linux: none"
+    );
+    let all = renderer(
+      "linux",
+      &[
+        ("WEBKIT_DISABLE_DMABUF_RENDERER", "1"),
+        ("LIBGL_ALWAYS_SOFTWARE", "1"),
+        ("P3DM_WEBKIT_NO_COMPOSITING", "1"),
+      ],
+    );
+    assert_eq!(
+      all.to_string(),
+      "WebKit toggles on linux: DMABUF renderer disabled, compositing disabled, software OpenGL"
+    );
+    assert_eq!(
+      renderer("macos", &[("LIBGL_ALWAYS_SOFTWARE", "1")]).to_string(),
+      "WebKit toggles on macos: none"
+    );
+  }
+
+  #[test]
+  fn load_and_solve_rejects_dropped_tags() {
+    let maze = Maze::generate_with_seed((3, 3, 1), 8, Algorithm::Prim).unwrap();
+    let mut json: serde_json::Value = serde_json::from_str(&maze.to_json()).unwrap();
+    json["tags"] = serde_json::json!([{ "cell": [3, 3, 0], "tag": "spawn" }]);
+    let e = load_and_solve(json.to_string(), [0, 0, 0], [2, 2, 0]).unwrap_err();
+    assert!(e.starts_with("invalid: the tag on [3, 3, 0]"), "{e}");
+  }
+
+  #[test]
+  fn the_graph_has_one_edge_per_passage() {
+    let dto = generate(7, 6, 3, "kruskal").unwrap();
+    let graph = maze_graph(dto.clone()).unwrap();
+    assert_eq!(graph.nodes.len(), 7 * 6 * 3);
+    assert_eq!(graph.edges.len(), passages(&dto));
+    let mut unique = graph.edges.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), graph.edges.len());
+    for [a, b] in &graph.edges {
+      assert!(a < b && *b < graph.nodes.len());
+      let moved: u32 = (0..3)
+        .map(|axis| graph.nodes[*a].coord[axis].abs_diff(graph.nodes[*b].coord[axis]))
+        .sum();
+      assert_eq!(moved, 1);
+    }
+  }
+
+  #[test]
+  fn the_graph_leaves_out_masked_cells() {
+    // An L: the top-right quarter of a 4x4 level is solid.
+    let flags = (0..16)
+      .map(|index| !(index % 4 >= 2 && index / 4 < 2))
+      .collect();
+    let mask = Mask::from_flags(4, 4, 1, flags).unwrap();
+    let maze = Maze::generate_masked(&mask, Algorithm::Prim, 3).unwrap();
+    let graph = maze_graph(MazeDto::from(&maze)).unwrap();
+    assert_eq!(graph.nodes.len(), 12);
+    assert!(graph.nodes.iter().all(|node| maze.is_active(node.coord)));
+    assert!(graph
+      .nodes
+      .iter()
+      .all(|node| maze.coord(node.cell) == node.coord));
+    assert_eq!(graph.edges.len(), 11);
+  }
+
+  #[test]
+  fn the_graph_keeps_the_corridors_of_a_crossing_apart() {
+    let mut maze = Maze::new(3, 3, 1);
+    for dir in [
+      Direction::East,
+      Direction::West,
+      Direction::South,
+      Direction::North,
+    ] {
+      maze.carve([1, 1, 0], dir);
+    }
+    assert!(maze.add_crossing([1, 1, 0]));
+    let graph = maze_graph(MazeDto::from(&maze)).unwrap();
+    assert_eq!(graph.nodes.len(), 10);
+    assert_eq!(graph.nodes[9].coord, [1, 1, 0]);
+    let mut edges = graph.edges.clone();
+    edges.sort();
+    assert_eq!(edges, [[1, 9], [3, 4], [4, 5], [7, 9]]);
+  }
+
+  #[test]
+  fn the_graph_follows_wrapped_edges() {
+    let mut maze = Maze::new(3, 1, 1);
+    maze.set_wrap(WrapMode::WrapX);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([2, 0, 0], Direction::East);
+    let graph = maze_graph(MazeDto::from(&maze)).unwrap();
+    let mut edges = graph.edges.clone();
+    edges.sort();
+    assert_eq!(edges, [[0, 1], [0, 2]]);
+  }
+}
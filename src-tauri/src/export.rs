@@ -0,0 +1,473 @@
+//! Mesh exporters, plus top-down PNGs and CSV solution paths.
+//!
+//! Geometry is Y-up to match three.js and Blender's OBJ importer defaults: maze `x` maps to
+//! world X, the level `z` to world Y and maze `y` to world Z. Walls are zero-thickness quads
+//! whose normal faces the cell that owns them, or solid boxes with outward normals when
+//! [`MeshOptions::wall_thickness`] is set.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::maze::{Coord, Direction, Maze, WrapMode};
+
+mod glb;
+mod png;
+
+pub use glb::to_gltf;
+pub use png::{to_png, to_png_with_markers};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportError {
+  /// The output format's encoder rejected the data.
+  Encode(String),
+  /// The requested level is not part of the maze.
+  NoSuchLevel { level: u32, depth: u32 },
+  /// The image scale is too small to draw walls at, or too large for the maze.
+  BadScale(u32),
+}
+
+impl fmt::Display for ExportError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ExportError::Encode(reason) => write!(f, "failed to encode export: {reason}"),
+      ExportError::NoSuchLevel { level, depth } => {
+        write!(
+          f,
+          "level {level} does not exist in a maze {depth} levels deep"
+        )
+      }
+      ExportError::BadScale(scale) => write!(f, "cannot draw the maze at {scale} pixels per cell"),
+    }
+  }
+}
+
+impl std::error::Error for ExportError {}
+
+/// What to emit besides the walls, and at which scale.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MeshOptions {
+  /// Extent of one cell in world units along the maze's `[x, y, z]` axes, so `cell_size[2]`
+  /// is the height of a level. A single number is accepted for cubic cells.
+  #[serde(deserialize_with = "cell_size")]
+  pub cell_size: [f32; 3],
+  /// Emit the floor of every cell that has one.
+  pub floors: bool,
+  /// Emit the ceiling of every cell that has one.
+  pub ceilings: bool,
+  /// Width of solid walls in world units, centred on the grid lines and capped at the cell
+  /// size. `0.0` keeps the walls planar; floors and ceilings stay planar either way.
+  pub wall_thickness: f32,
+}
+
+impl Default for MeshOptions {
+  fn default() -> Self {
+    MeshOptions {
+      cell_size: [1.0; 3],
+      floors: true,
+      ceilings: false,
+      wall_thickness: 0.0,
+    }
+  }
+}
+
+impl MeshOptions {
+  /// World position of a lattice corner; see the module docs for the axis mapping and
+  /// [`line`] for the planar axes.
+  fn position(&self, point: [u32; 3]) -> [f32; 3] {
+    let [x, y, z] = self.cell_size;
+    let half = self.thickness() / 2.0;
+    let planar = |v: u32, size: f32| (v / 3) as f32 * size + ((v % 3) as f32 - 1.0) * half;
+    [
+      planar(point[0], x),
+      point[1] as f32 * z,
+      planar(point[2], y),
+    ]
+  }
+
+  /// The wall thickness actually used; negative and NaN thicknesses count as planar.
+  fn thickness(&self) -> f32 {
+    if self.wall_thickness > 0.0 {
+      self
+        .wall_thickness
+        .min(self.cell_size[0])
+        .min(self.cell_size[1])
+    } else {
+      0.0
+    }
+  }
+}
+
+/// Reads [`MeshOptions::cell_size`] as either three sizes or one for every axis.
+fn cell_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[f32; 3], D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum CellSize {
+    Cubic(f32),
+    Axes([f32; 3]),
+  }
+  Ok(match CellSize::deserialize(deserializer)? {
+    CellSize::Cubic(size) => [size; 3],
+    CellSize::Axes(sizes) => sizes,
+  })
+}
+
+/// Lattice coordinate of grid line `k` on world X or Z.
+///
+/// These axes are counted in thirds so solid walls can sit on either side of a line: `3k + 1`
+/// is the line itself and `3k`, `3k + 2` are the faces of a wall on it. World Y counts levels.
+const fn line(k: u32) -> u32 {
+  3 * k + 1
+}
+
+/// Rectangular face on the world lattice, corners wound counter-clockwise around `normal`.
+struct Quad {
+  corners: [[u32; 3]; 4],
+  normal: [i8; 3],
+}
+
+impl Quad {
+  fn triangles(&self) -> [[[u32; 3]; 3]; 2] {
+    let [a, b, c, d] = self.corners;
+    [[a, b, c], [a, c, d]]
+  }
+}
+
+/// Collects every face of `maze` selected by `opts`.
+fn quads(maze: &Maze, opts: &MeshOptions) -> Vec<Quad> {
+  let mut quads = planar_quads(maze, opts);
+  if opts.thickness() > 0.0 {
+    quads.retain(|quad| quad.normal[1] != 0);
+    quads.extend(solid_quads(maze));
+  }
+  quads
+}
+
+/// Zero-thickness walls, floors and ceilings.
+///
+/// A wall shared by two cells is emitted once, by the cell west or north of it, so only the
+/// east and south walls of each cell plus the west and north boundary are visited. Masked cells
+/// emit nothing; the active cell beside them supplies the wall instead.
+fn planar_quads(maze: &Maze, opts: &MeshOptions) -> Vec<Quad> {
+  let mut quads = Vec::new();
+  for index in 0..maze.cell_count() {
+    let cell = maze.coord(index);
+    if !maze.is_active(cell) {
+      continue;
+    }
+    let [x, y, z] = cell;
+    // The mesh lies flat, so faces joining a wrapped edge to the opposite one are drawn on
+    // both edges.
+    let exposed = |dir| {
+      maze
+        .step(cell, dir, WrapMode::None)
+        .is_none_or(|next| !maze.is_active(next))
+    };
+    let faces = [
+      (Direction::East, true),
+      (Direction::South, true),
+      (Direction::West, exposed(Direction::West)),
+      (Direction::North, exposed(Direction::North)),
+      (Direction::Down, opts.floors),
+      (Direction::Up, opts.ceilings),
+    ];
+    for (dir, wanted) in faces {
+      if wanted && maze.has_wall(cell, dir) {
+        let (axis, far) = match dir {
+          Direction::East => (0, true),
+          Direction::West => (0, false),
+          Direction::Up => (1, true),
+          Direction::Down => (1, false),
+          Direction::South => (2, true),
+          Direction::North => (2, false),
+        };
+        let min = [line(x), z, line(y)];
+        let max = [line(x + 1), z + 1, line(y + 1)];
+        quads.push(side(min, max, axis, far, true));
+      }
+    }
+  }
+  quads
+}
+
+/// Walls as boxes, leaving out every face that touches another box.
+///
+/// Each level is cut into a `(2 * width + 1) x (2 * height + 1)` grid of pieces: posts on the
+/// grid corners, wall segments between them and the open cell interiors. A segment is solid
+/// where an active cell beside it has that wall, and a post where any segment touching it is.
+fn solid_quads(maze: &Maze) -> Vec<Quad> {
+  let (width, height, depth) = maze.dimensions();
+  let (cols, rows) = (2 * width as usize + 1, 2 * height as usize + 1);
+  let piece = |i: usize, j: usize, z: usize| i + j * cols + z * cols * rows;
+  let walled = |x: usize, y: usize, z: usize, dir| {
+    let cell = [x as u32, y as u32, z as u32];
+    maze.is_active(cell) && maze.has_wall(cell, dir)
+  };
+  let mut solid = vec![false; cols * rows * depth as usize];
+  for z in 0..depth as usize {
+    for j in 0..rows {
+      for i in 0..cols {
+        let (x, y) = (i / 2, j / 2);
+        solid[piece(i, j, z)] = match (i % 2, j % 2) {
+          (0, 1) => {
+            (x > 0 && walled(x - 1, y, z, Direction::East)) || walled(x, y, z, Direction::West)
+          }
+          (1, 0) => {
+            (y > 0 && walled(x, y - 1, z, Direction::South)) || walled(x, y, z, Direction::North)
+          }
+          _ => false,
+        };
+      }
+    }
+    for j in (0..rows).step_by(2) {
+      for i in (0..cols).step_by(2) {
+        solid[piece(i, j, z)] = (i > 0 && solid[piece(i - 1, j, z)])
+          || (i + 1 < cols && solid[piece(i + 1, j, z)])
+          || (j > 0 && solid[piece(i, j - 1, z)])
+          || (j + 1 < rows && solid[piece(i, j + 1, z)]);
+      }
+    }
+  }
+
+  // Posts span a wall's width around their line, interiors the rest of the cell.
+  let start = |i: usize| (3 * (i / 2) + 2 * (i % 2)) as u32;
+  let mut quads = Vec::new();
+  for z in 0..depth as usize {
+    for j in 0..rows {
+      for i in 0..cols {
+        if !solid[piece(i, j, z)] {
+          continue;
+        }
+        let min = [start(i), z as u32, start(j)];
+        let max = [start(i + 1), z as u32 + 1, start(j + 1)];
+        let mut open = |axis: usize, far: bool, neighbour: Option<(usize, usize, usize)>| {
+          if neighbour.is_none_or(|(i, j, z)| !solid[piece(i, j, z)]) {
+            quads.push(side(min, max, axis, far, false));
+          }
+        };
+        open(0, false, i.checked_sub(1).map(|i| (i, j, z)));
+        open(0, true, (i + 1 < cols).then_some((i + 1, j, z)));
+        open(1, false, z.checked_sub(1).map(|z| (i, j, z)));
+        open(1, true, (z + 1 < depth as usize).then_some((i, j, z + 1)));
+        open(2, false, j.checked_sub(1).map(|j| (i, j, z)));
+        open(2, true, (j + 1 < rows).then_some((i, j + 1, z)));
+      }
+    }
+  }
+  quads
+}
+
+/// The side of the box `min..max` facing `-axis`, or `+axis` when `far`, with its normal
+/// pointing into the box when `inward`.
+fn side(min: [u32; 3], max: [u32; 3], axis: usize, far: bool, inward: bool) -> Quad {
+  let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+  let corner = |pu: u32, pv: u32| {
+    let mut point = min;
+    point[axis] = if far { max[axis] } else { min[axis] };
+    point[u] = pu;
+    point[v] = pv;
+    point
+  };
+  // (u, v) is a right-handed pair around `axis`, so this winding faces +axis.
+  let mut corners = [
+    corner(min[u], min[v]),
+    corner(max[u], min[v]),
+    corner(max[u], max[v]),
+    corner(min[u], max[v]),
+  ];
+  let mut normal = [0; 3];
+  if far == inward {
+    corners.reverse();
+    normal[axis] = -1;
+  } else {
+    normal[axis] = 1;
+  }
+  Quad { corners, normal }
+}
+
+/// Wavefront OBJ text for `maze`.
+///
+/// Positions are shared between every face touching the same lattice corner and each face
+/// references one of the six axis normals, so adjacent walls never duplicate vertices.
+pub fn to_obj(maze: &Maze, opts: &MeshOptions) -> String {
+  let (width, height, depth) = maze.dimensions();
+  let mut positions: HashMap<[u32; 3], usize> = HashMap::new();
+  let mut normals: HashMap<[i8; 3], usize> = HashMap::new();
+  let mut vertices = String::new();
+  let mut normal_lines = String::new();
+  let mut faces = String::new();
+
+  for quad in quads(maze, opts) {
+    let next = normals.len() + 1;
+    let normal = *normals.entry(quad.normal).or_insert_with(|| {
+      let [x, y, z] = quad.normal;
+      let _ = writeln!(normal_lines, "vn {x} {y} {z}");
+      next
+    });
+    for triangle in quad.triangles() {
+      faces.push('f');
+      for point in triangle {
+        let next = positions.len() + 1;
+        let vertex = *positions.entry(point).or_insert_with(|| {
+          let [x, y, z] = opts.position(point);
+          let _ = writeln!(vertices, "v {x} {y} {z}");
+          next
+        });
+        let _ = write!(faces, " {vertex}//{normal}");
+      }
+      faces.push('\n');
+    }
+  }
+
+  format!("# procedural-3d-maze {width}x{height}x{depth}\no maze\n{vertices}{normal_lines}{faces}")
+}
+
+/// `path` as CSV: an `index,x,y,z` header, then one row per cell numbered from `0`. An empty
+/// path gives the header alone.
+pub fn path_to_csv(path: &[Coord]) -> String {
+  let mut csv = String::from("index,x,y,z\n");
+  for (index, [x, y, z]) in path.iter().enumerate() {
+    let _ = writeln!(csv, "{index},{x},{y},{z}");
+  }
+  csv
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A 2x2x1 maze open everywhere but between `[0, 1, 0]` and `[1, 1, 0]`.
+  fn square() -> Maze {
+    let mut maze = Maze::new(2, 2, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([0, 0, 0], Direction::South);
+    maze.carve([1, 0, 0], Direction::South);
+    maze
+  }
+
+  fn faces(obj: &str) -> usize {
+    obj.lines().filter(|line| line.starts_with("f ")).count()
+  }
+
+  #[test]
+  fn obj_has_two_triangles_per_wall() {
+    let walls_only = MeshOptions {
+      floors: false,
+      ..MeshOptions::default()
+    };
+    // Eight boundary walls and one inner wall.
+    assert_eq!(faces(&to_obj(&square(), &walls_only)), 18);
+    assert_eq!(faces(&to_obj(&square(), &MeshOptions::default())), 18 + 8);
+  }
+
+  #[test]
+  fn scales_each_axis_independently() {
+    let opts = MeshOptions {
+      cell_size: [2.0, 3.0, 0.5],
+      ..MeshOptions::default()
+    };
+    // Maze y runs along world Z and levels along world Y.
+    assert_eq!(opts.position([line(1), 2, line(3)]), [2.0, 1.0, 9.0]);
+    let obj = to_obj(&square(), &opts);
+    assert!(obj.lines().any(|line| line == "v 4 0.5 6"), "{obj}");
+    assert!(!obj.lines().any(|line| line == "v 4 1 4"));
+  }
+
+  #[test]
+  fn solid_walls_are_closed_boxes() {
+    let solid = MeshOptions {
+      floors: false,
+      wall_thickness: 0.2,
+      ..MeshOptions::default()
+    };
+    // Four wall segments and four corner posts; the posts show only their outer sides.
+    let quads = quads(&Maze::new(1, 1, 1), &solid);
+    let facing = |normal: [i8; 3]| quads.iter().filter(|quad| quad.normal == normal).count();
+    assert_eq!(quads.len(), 32);
+    assert_eq!((facing([0, 1, 0]), facing([0, -1, 0])), (8, 8));
+    assert_eq!((facing([1, 0, 0]), facing([-1, 0, 0])), (4, 4));
+    let obj = to_obj(&Maze::new(1, 1, 1), &solid);
+    assert!(obj.lines().any(|line| line == "v -0.1 0 -0.1"), "{obj}");
+  }
+
+  #[test]
+  fn solid_walls_show_both_sides_of_every_wall() {
+    let maze = Maze::generate_with_seed((10, 10, 2), 3, crate::generator::Algorithm::Prim).unwrap();
+    let planar = MeshOptions {
+      floors: false,
+      ..MeshOptions::default()
+    };
+    let solid = MeshOptions {
+      wall_thickness: 0.2,
+      ..planar.clone()
+    };
+    let walls = quads(&maze, &planar).len();
+    let solid_quads = quads(&maze, &solid);
+    let sides: Vec<&Quad> = solid_quads
+      .iter()
+      .filter(|quad| quad.normal[1] == 0)
+      .collect();
+    // Segments start two units into each three-unit cell span, posts on its line.
+    let along = |quad: &Quad| {
+      let axis = if quad.normal[0] == 0 { 0 } else { 2 };
+      quad
+        .corners
+        .iter()
+        .map(|corner| corner[axis])
+        .min()
+        .unwrap()
+        % 3
+        == 2
+    };
+    let segments = sides.iter().filter(|quad| along(quad)).count();
+    assert_eq!(segments, 2 * walls);
+    let posts = 11 * 11 * 2;
+    assert!(
+      sides.len() - segments <= 4 * posts,
+      "{} post sides",
+      sides.len() - segments
+    );
+
+    // Two triangles per quad, and only the walls turn solid.
+    let faces = |opts: &MeshOptions| {
+      to_obj(&maze, opts)
+        .lines()
+        .filter(|line| line.starts_with("f "))
+        .count()
+    };
+    assert_eq!(faces(&planar), 2 * walls);
+    assert_eq!(faces(&solid), 2 * solid_quads.len());
+    assert!(faces(&solid) > 2 * faces(&planar));
+  }
+
+  #[test]
+  fn path_csv_numbers_each_cell() {
+    assert_eq!(path_to_csv(&[]), "index,x,y,z\n");
+    let path = [[0, 0, 0], [1, 0, 0], [1, 1, 0], [1, 1, 1]];
+    let csv = path_to_csv(&path);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), path.len() + 1);
+    assert_eq!(lines[0], "index,x,y,z");
+    for (i, (line, [x, y, z])) in lines[1..].iter().zip(path).enumerate() {
+      assert_eq!(*line, format!("{i},{x},{y},{z}"));
+    }
+  }
+
+  #[test]
+  fn wrapped_edges_are_walled_on_both_sides() {
+    let walls_only = MeshOptions {
+      floors: false,
+      ..MeshOptions::default()
+    };
+    let mut maze = Maze::new(4, 4, 1);
+    maze.set_wrap(WrapMode::WrapXY);
+    // Five grid lines of four walls each way, the seams included twice.
+    assert_eq!(faces(&to_obj(&maze, &walls_only)), 2 * 40);
+    assert_eq!(
+      faces(&to_obj(&maze, &walls_only)),
+      faces(&to_obj(&Maze::new(4, 4, 1), &walls_only))
+    );
+  }
+}
@@ -0,0 +1,221 @@
+//! Binary glTF (`.glb`) writer.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use gltf::binary::{Glb, Header};
+use gltf::json::{self, validation::Checked::Valid, validation::USize64};
+use serde_json::value::RawValue;
+
+use super::{quads, ExportError, MeshOptions};
+use crate::maze::Maze;
+
+/// Binary glTF scene holding one mesh with positions, normals and triangle indices.
+///
+/// The maze dimensions and seed are stored in the root `extras` so viewers can show them.
+pub fn to_gltf(maze: &Maze, opts: &MeshOptions) -> Result<Vec<u8>, ExportError> {
+  // glTF vertices carry their normal, so corners are only shared between coplanar faces.
+  let mut vertices: HashMap<([u32; 3], [i8; 3]), u32> = HashMap::new();
+  let mut positions: Vec<[f32; 3]> = Vec::new();
+  let mut normals: Vec<[f32; 3]> = Vec::new();
+  let mut indices: Vec<u32> = Vec::new();
+  for quad in quads(maze, opts) {
+    for triangle in quad.triangles() {
+      for point in triangle {
+        let index = *vertices.entry((point, quad.normal)).or_insert_with(|| {
+          positions.push(opts.position(point));
+          normals.push(quad.normal.map(f32::from));
+          positions.len() as u32 - 1
+        });
+        indices.push(index);
+      }
+    }
+  }
+
+  let mut bin = Vec::new();
+  for value in positions.iter().chain(&normals).flatten() {
+    bin.extend_from_slice(&value.to_le_bytes());
+  }
+  for index in &indices {
+    bin.extend_from_slice(&index.to_le_bytes());
+  }
+
+  let mut root = json::Root::default();
+  let buffer = root.push(json::Buffer {
+    byte_length: USize64::from(bin.len()),
+    extensions: None,
+    extras: None,
+    name: None,
+    uri: None,
+  });
+  let vertex_bytes = positions.len() * 12;
+  let mut view = |offset: usize, length: usize, target: json::buffer::Target| {
+    let stride = (target == json::buffer::Target::ArrayBuffer).then_some(json::buffer::Stride(12));
+    root.push(json::buffer::View {
+      buffer,
+      byte_length: USize64::from(length),
+      byte_offset: Some(USize64::from(offset)),
+      byte_stride: stride,
+      extensions: None,
+      extras: None,
+      name: None,
+      target: Some(Valid(target)),
+    })
+  };
+  let position_view = view(0, vertex_bytes, json::buffer::Target::ArrayBuffer);
+  let normal_view = view(
+    vertex_bytes,
+    vertex_bytes,
+    json::buffer::Target::ArrayBuffer,
+  );
+  let index_view = view(
+    vertex_bytes * 2,
+    indices.len() * 4,
+    json::buffer::Target::ElementArrayBuffer,
+  );
+
+  let (min, max) = bounds(&positions);
+  let positions = root.push(accessor(
+    position_view,
+    positions.len(),
+    json::accessor::ComponentType::F32,
+    json::accessor::Type::Vec3,
+    Some((min.to_vec(), max.to_vec())),
+  ));
+  let normals = root.push(accessor(
+    normal_view,
+    normals.len(),
+    json::accessor::ComponentType::F32,
+    json::accessor::Type::Vec3,
+    None,
+  ));
+  let indices = root.push(accessor(
+    index_view,
+    indices.len(),
+    json::accessor::ComponentType::U32,
+    json::accessor::Type::Scalar,
+    None,
+  ));
+
+  let mesh = root.push(json::Mesh {
+    extensions: None,
+    extras: None,
+    name: Some("maze".to_owned()),
+    primitives: vec![json::mesh::Primitive {
+      attributes: BTreeMap::from([
+        (Valid(json::mesh::Semantic::Positions), positions),
+        (Valid(json::mesh::Semantic::Normals), normals),
+      ]),
+      extensions: None,
+      extras: None,
+      indices: Some(indices),
+      material: None,
+      mode: Valid(json::mesh::Mode::Triangles),
+      targets: None,
+    }],
+    weights: None,
+  });
+  let node = root.push(json::Node {
+    mesh: Some(mesh),
+    name: Some("maze".to_owned()),
+    ..Default::default()
+  });
+  let scene = root.push(json::Scene {
+    extensions: None,
+    extras: None,
+    name: None,
+    nodes: vec![node],
+  });
+  root.scene = Some(scene);
+  root.asset.generator = Some(format!("procedural-3d-maze {}", env!("CARGO_PKG_VERSION")));
+  root.extras = Some(extras(maze)?);
+
+  let json = root
+    .to_vec()
+    .map_err(|e| ExportError::Encode(e.to_string()))?;
+  Glb {
+    header: Header {
+      magic: *b"glTF",
+      version: 2,
+      // Recomputed by the writer.
+      length: 0,
+    },
+    json: Cow::Owned(json),
+    bin: Some(Cow::Owned(bin)),
+  }
+  .to_vec()
+  .map_err(|e| ExportError::Encode(e.to_string()))
+}
+
+fn accessor(
+  view: json::Index<json::buffer::View>,
+  count: usize,
+  component: json::accessor::ComponentType,
+  kind: json::accessor::Type,
+  bounds: Option<(Vec<f32>, Vec<f32>)>,
+) -> json::Accessor {
+  let (min, max) = bounds.unzip();
+  json::Accessor {
+    buffer_view: Some(view),
+    byte_offset: Some(USize64(0)),
+    count: USize64::from(count),
+    component_type: Valid(json::accessor::GenericComponentType(component)),
+    extensions: None,
+    extras: None,
+    type_: Valid(kind),
+    min: min.map(json::Value::from),
+    max: max.map(json::Value::from),
+    name: None,
+    normalized: false,
+    sparse: None,
+  }
+}
+
+/// Component-wise bounds of `points`, required by glTF for position accessors.
+fn bounds(points: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+  let mut min = [f32::INFINITY; 3];
+  let mut max = [f32::NEG_INFINITY; 3];
+  for point in points {
+    for axis in 0..3 {
+      min[axis] = min[axis].min(point[axis]);
+      max[axis] = max[axis].max(point[axis]);
+    }
+  }
+  (min, max)
+}
+
+fn extras(maze: &Maze) -> Result<Box<RawValue>, ExportError> {
+  let metadata = serde_json::json!({
+    "width": maze.width(),
+    "height": maze.height(),
+    "depth": maze.depth(),
+    "seed": maze.seed(),
+  });
+  serde_json::value::to_raw_value(&metadata).map_err(|e| ExportError::Encode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+
+  #[test]
+  fn writes_a_glb_that_parses_back() {
+    let maze = Maze::generate_with_seed((4, 3, 2), 9, Algorithm::Prim).unwrap();
+    let glb = to_gltf(&maze, &MeshOptions::default()).unwrap();
+    assert_eq!(&glb[..4], b"glTF");
+    let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+    let extras = gltf.document.as_json().extras.as_ref().unwrap().get();
+    assert!(extras.contains("\"seed\":9"), "{extras}");
+    let triangles = quads(&maze, &MeshOptions::default()).len() * 2;
+    let primitive = gltf
+      .document
+      .meshes()
+      .next()
+      .unwrap()
+      .primitives()
+      .next()
+      .unwrap();
+    assert_eq!(primitive.indices().unwrap().count(), triangles * 3);
+  }
+}
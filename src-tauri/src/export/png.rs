@@ -0,0 +1,169 @@
+//! Top-down PNG previews, one level at a time.
+
+use std::io::Cursor;
+
+use image::{ImageFormat, Rgb, RgbImage};
+
+use super::ExportError;
+use crate::maze::{Coord, Direction, Maze};
+
+const WALL: Rgb<u8> = Rgb([0, 0, 0]);
+const PASSAGE: Rgb<u8> = Rgb([255, 255, 255]);
+const STAIRS_UP: Rgb<u8> = Rgb([255, 170, 0]);
+const STAIRS_DOWN: Rgb<u8> = Rgb([0, 160, 200]);
+const STAIRS_BOTH: Rgb<u8> = Rgb([190, 70, 220]);
+const ENTRANCE: Rgb<u8> = Rgb([40, 180, 70]);
+const EXIT: Rgb<u8> = Rgb([220, 40, 40]);
+
+/// Largest image drawn, about 200 MB of pixels.
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// Smallest scale with room for a wall line on both sides of a passage.
+const MIN_SCALE: u32 = 3;
+
+/// PNG of `level` seen from above, `scale` pixels per cell, so the image is
+/// `width * scale` by `height * scale`. Scales below 3 leave no room for the walls and are
+/// rejected.
+///
+/// Walls are black lines along the cell edges and passages white. Cells with a stair up,
+/// down or both get their own fill colour, and masked cells are solid black.
+pub fn to_png(maze: &Maze, level: u32, scale: u32) -> Result<Vec<u8>, ExportError> {
+  encode(render(maze, level, scale, false)?)
+}
+
+/// [`to_png`] with the entrance marked green and the exit red when they lie on `level`.
+pub fn to_png_with_markers(maze: &Maze, level: u32, scale: u32) -> Result<Vec<u8>, ExportError> {
+  encode(render(maze, level, scale, true)?)
+}
+
+fn render(maze: &Maze, level: u32, scale: u32, markers: bool) -> Result<RgbImage, ExportError> {
+  let (width, height, depth) = maze.dimensions();
+  if level >= depth {
+    return Err(ExportError::NoSuchLevel { level, depth });
+  }
+  let size = width.checked_mul(scale).zip(height.checked_mul(scale));
+  let fits = |&(w, h): &(u32, u32)| scale >= MIN_SCALE && u64::from(w) * u64::from(h) <= MAX_PIXELS;
+  let Some((image_width, image_height)) = size.filter(fits) else {
+    return Err(ExportError::BadScale(scale));
+  };
+
+  let mut image = RgbImage::from_pixel(image_width, image_height, WALL);
+  // Each cell draws its half of a shared wall.
+  let line = (scale / 8).max(1);
+  for y in 0..height {
+    for x in 0..width {
+      let cell = [x, y, level];
+      if !maze.is_active(cell) {
+        continue;
+      }
+      let up = !maze.has_wall(cell, Direction::Up);
+      let down = !maze.has_wall(cell, Direction::Down);
+      let fill = match (up, down) {
+        (true, true) => STAIRS_BOTH,
+        (true, false) => STAIRS_UP,
+        (false, true) => STAIRS_DOWN,
+        (false, false) => PASSAGE,
+      };
+      let (left, top) = (x * scale, y * scale);
+      // The corner pillars stay black so wall lines always meet.
+      let inner = |offset: u32| offset >= line && offset < scale - line;
+      for dy in 0..scale {
+        for dx in 0..scale {
+          let open = match (inner(dx), inner(dy)) {
+            (true, true) => true,
+            (false, true) => !maze.has_wall(cell, horizontal_side(dx, line)),
+            (true, false) => !maze.has_wall(cell, vertical_side(dy, line)),
+            (false, false) => false,
+          };
+          if open {
+            image.put_pixel(left + dx, top + dy, fill);
+          }
+        }
+      }
+    }
+  }
+
+  if markers {
+    for (cell, colour) in [(maze.entrance(), ENTRANCE), (maze.exit(), EXIT)] {
+      // Hand-edited mazes may put a portal outside the grid, which has no pixels to mark.
+      if cell[2] == level && maze.contains(cell) {
+        mark(&mut image, cell, scale, colour);
+      }
+    }
+  }
+  Ok(image)
+}
+
+/// The side a pixel column `dx` in the edge band belongs to.
+fn horizontal_side(dx: u32, line: u32) -> Direction {
+  if dx < line {
+    Direction::West
+  } else {
+    Direction::East
+  }
+}
+
+/// The side a pixel row `dy` in the edge band belongs to.
+fn vertical_side(dy: u32, line: u32) -> Direction {
+  if dy < line {
+    Direction::North
+  } else {
+    Direction::South
+  }
+}
+
+/// Fills the middle half of `cell` with `colour`.
+fn mark(image: &mut RgbImage, [x, y, _]: Coord, scale: u32, colour: Rgb<u8>) {
+  let inset = scale / 4;
+  for dy in inset..scale - inset {
+    for dx in inset..scale - inset {
+      image.put_pixel(x * scale + dx, y * scale + dy, colour);
+    }
+  }
+}
+
+fn encode(image: RgbImage) -> Result<Vec<u8>, ExportError> {
+  let mut bytes = Vec::new();
+  image
+    .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+    .map_err(|e| ExportError::Encode(e.to_string()))?;
+  Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+
+  #[test]
+  fn skips_markers_outside_the_grid() {
+    let mut maze = Maze::new(3, 3, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.exit = [9, 0, 0];
+    let image = render(&maze, 0, 8, true).unwrap();
+    assert_eq!(*image.get_pixel(4, 4), ENTRANCE);
+    assert!(image.pixels().all(|&pixel| pixel != EXIT));
+  }
+
+  #[test]
+  fn encodes_an_image_of_the_requested_size() {
+    let maze = Maze::generate_with_seed((7, 4, 2), 1, Algorithm::Prim).unwrap();
+    for scale in [3, 10] {
+      let png = to_png(&maze, 1, scale).unwrap();
+      let image = image::load_from_memory(&png).unwrap();
+      assert_eq!((image.width(), image.height()), (7 * scale, 4 * scale));
+    }
+  }
+
+  #[test]
+  fn rejects_scales_too_small_for_walls() {
+    let maze = Maze::new(2, 2, 1);
+    for scale in [0, 1, 2] {
+      assert_eq!(to_png(&maze, 0, scale), Err(ExportError::BadScale(scale)));
+    }
+    assert_eq!(
+      to_png(&maze, 1, 8),
+      Err(ExportError::NoSuchLevel { level: 1, depth: 1 })
+    );
+  }
+}
@@ -0,0 +1,451 @@
+//! Versioned on-disk format for saved mazes.
+//!
+//! Every document carries a `version`. Older layouts are migrated on load; versions newer
+//! than [`MazeFile::CURRENT_VERSION`] are rejected rather than guessed at. Large mazes can use
+//! the bit-packed [`Maze::to_bytes`] layout instead.
+//!
+//! A file written by [`MazeFile::generated`] also records how the maze was carved, so
+//! [`regenerate_from_file`] can carve it again.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{
+  Algorithm, Bias, DifficultyGradient, GenConfig, GenerateError, LockConfig, PortalStrategy,
+  RngKind, Symmetry, VerticalConfig,
+};
+use crate::maze::{
+  self, Coord, Lock, Mask, Maze, TaggedCell, Topology, ValidationError, WrapMode, ALL_WALLS,
+};
+
+mod binary;
+
+/// Current on-disk layout: flat wall masks in [`Maze::index`] order.
+///
+/// Version 2 is the same layout without `wrap`, so it loads as is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MazeFile {
+  pub version: u32,
+  pub width: u32,
+  pub height: u32,
+  pub depth: u32,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seed: Option<u64>,
+  /// Defaults to the `[0, 0, 0]` corner when missing.
+  #[serde(default)]
+  pub entrance: Option<Coord>,
+  /// Defaults to the far corner when missing.
+  #[serde(default)]
+  pub exit: Option<Coord>,
+  pub walls: Vec<u8>,
+  /// Active-cell flags in `walls` order; absent for full-block mazes.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mask: Option<Vec<bool>>,
+  /// Tags on cells that are missing or masked off are dropped on load.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<TaggedCell>,
+  /// Weave crossings; see [`Maze::is_crossing`].
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub crossings: Vec<Coord>,
+  /// Locked doors and their keys; see [`Lock`].
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub locks: Vec<Lock>,
+  #[serde(default, skip_serializing_if = "WrapMode::is_none")]
+  pub wrap: WrapMode,
+  /// How the maze was carved from `seed`; absent for hand-made or edited mazes.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub recipe: Option<Recipe>,
+}
+
+impl MazeFile {
+  pub const CURRENT_VERSION: u32 = 3;
+
+  /// File for a maze just generated from `config` under `topology`, recording the recipe.
+  pub fn generated(maze: &Maze, config: &GenConfig, topology: &dyn Topology) -> MazeFile {
+    MazeFile {
+      recipe: Some(Recipe {
+        rng: config.rng.clone(),
+        algorithm: config.algorithm,
+        vertical: config.vertical,
+        braiding: config.braiding,
+        gradient: config.gradient,
+        portals: config.portals,
+        bias: config.bias,
+        weave: config.weave,
+        symmetry: config.symmetry,
+        locks: config.locks,
+        topology: topology.id().to_owned(),
+      }),
+      ..MazeFile::from(maze)
+    }
+  }
+}
+
+/// The [`GenConfig`] options a generated maze was carved with, besides the dimensions, seed
+/// and mask the file already holds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipe {
+  pub rng: RngKind,
+  pub algorithm: Algorithm,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub vertical: Option<VerticalConfig>,
+  #[serde(default)]
+  pub braiding: f32,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub gradient: Option<DifficultyGradient>,
+  #[serde(default)]
+  pub portals: PortalStrategy,
+  #[serde(default)]
+  pub bias: Bias,
+  #[serde(default)]
+  pub weave: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub symmetry: Option<Symmetry>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub locks: Option<LockConfig>,
+  /// A [`Topology::id`].
+  pub topology: String,
+}
+
+/// Carves the maze `file` records again from its seed and [`Recipe`], then restores its tags,
+/// returning any dropped for sitting outside the maze.
+///
+/// Fails with [`LoadError::Irreproducible`] when the file has no recipe or seed, when this
+/// build lacks the recorded RNG or topology, or when the walls or mask it carves differ from
+/// the file's, rather than returning a different maze.
+pub fn regenerate_from_file(file: &MazeFile) -> Result<(Maze, Vec<ValidationError>), LoadError> {
+  let (Some(recipe), Some(seed)) = (&file.recipe, file.seed) else {
+    return Err(LoadError::Irreproducible(
+      "the file does not record how it was generated".to_owned(),
+    ));
+  };
+  let topology = maze::topology(&recipe.topology)
+    .ok_or_else(|| LoadError::Irreproducible(format!("unknown topology '{}'", recipe.topology)))?;
+  let dims = (file.width, file.height, file.depth);
+  let mask = file
+    .mask
+    .clone()
+    .map(|flags| {
+      Mask::from_flags(dims.0, dims.1, dims.2, flags)
+        .ok_or_else(|| LoadError::Parse("mask does not match the maze size".to_owned()))
+    })
+    .transpose()?;
+  let config = GenConfig {
+    rng: recipe.rng.clone(),
+    vertical: recipe.vertical,
+    mask,
+    braiding: recipe.braiding,
+    gradient: recipe.gradient,
+    portals: recipe.portals,
+    bias: recipe.bias,
+    weave: recipe.weave,
+    symmetry: recipe.symmetry,
+    locks: recipe.locks,
+    wrap: file.wrap,
+    ..GenConfig::new(dims, recipe.algorithm, seed)
+  };
+  let mut maze = Maze::generate_in(&config, topology)
+    .map_err(|e: GenerateError| LoadError::Irreproducible(e.to_string()))?;
+  let walls = file.walls.iter().map(|w| w & ALL_WALLS);
+  if !maze.wall_data().iter().copied().eq(walls)
+    || maze.mask().map(Mask::flags) != file.mask.as_deref()
+  {
+    return Err(LoadError::Irreproducible(
+      "the recipe carves different walls than the file holds".to_owned(),
+    ));
+  }
+  let dropped = maze.restore_annotations(file.tags.clone(), Vec::new(), Vec::new());
+  Ok((maze, dropped))
+}
+
+/// Version 1 layout: wall masks nested as `cells[z][y][x]`, without a seed.
+#[derive(Deserialize)]
+struct MazeFileV1 {
+  cells: Vec<Vec<Vec<u8>>>,
+}
+
+impl MazeFileV1 {
+  fn migrate(self) -> Result<MazeFile, LoadError> {
+    let depth = self.cells.len();
+    let height = self.cells.first().map_or(0, Vec::len);
+    let width = self
+      .cells
+      .first()
+      .and_then(|rows| rows.first())
+      .map_or(0, Vec::len);
+    let ragged = self
+      .cells
+      .iter()
+      .any(|rows| rows.len() != height || rows.iter().any(|row| row.len() != width));
+    if ragged {
+      return Err(LoadError::Parse(
+        "version 1 cells must form a rectangular grid".to_owned(),
+      ));
+    }
+    Ok(MazeFile {
+      version: MazeFile::CURRENT_VERSION,
+      width: width as u32,
+      height: height as u32,
+      depth: depth as u32,
+      seed: None,
+      entrance: None,
+      exit: None,
+      walls: self.cells.into_iter().flatten().flatten().collect(),
+      mask: None,
+      tags: Vec::new(),
+      crossings: Vec::new(),
+      locks: Vec::new(),
+      wrap: WrapMode::None,
+      recipe: None,
+    })
+  }
+}
+
+#[derive(Deserialize)]
+struct VersionProbe {
+  version: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+  /// The document is not valid JSON or does not match its version's layout.
+  Parse(String),
+  /// The document was written by a newer (or unknown) format version.
+  UnsupportedVersion(u32),
+  /// The grid is empty or its length disagrees with the declared dimensions.
+  GridMismatch { expected: usize, found: usize },
+  /// [`regenerate_from_file`] cannot carve the same maze again, for the given reason.
+  Irreproducible(String),
+}
+
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LoadError::Parse(reason) => write!(f, "malformed maze file: {reason}"),
+      LoadError::UnsupportedVersion(version) => write!(
+        f,
+        "unsupported maze file version {version} (this build reads up to {})",
+        MazeFile::CURRENT_VERSION
+      ),
+      LoadError::GridMismatch { expected, found } => write!(
+        f,
+        "maze file declares {expected} cells but carries {found} wall masks"
+      ),
+      LoadError::Irreproducible(reason) => write!(f, "cannot regenerate maze: {reason}"),
+    }
+  }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<&Maze> for MazeFile {
+  fn from(maze: &Maze) -> Self {
+    MazeFile {
+      version: MazeFile::CURRENT_VERSION,
+      width: maze.width(),
+      height: maze.height(),
+      depth: maze.depth(),
+      seed: maze.seed(),
+      entrance: Some(maze.entrance()),
+      exit: Some(maze.exit()),
+      walls: maze.wall_data().to_vec(),
+      mask: maze.mask().map(|mask| mask.flags().to_vec()),
+      tags: maze.tagged_cells(),
+      crossings: maze.crossings(),
+      locks: maze.locks().to_vec(),
+      wrap: maze.wrap(),
+      recipe: None,
+    }
+  }
+}
+
+impl TryFrom<MazeFile> for Maze {
+  type Error = LoadError;
+
+  fn try_from(file: MazeFile) -> Result<Self, Self::Error> {
+    file.into_maze().map(|(maze, _dropped)| maze)
+  }
+}
+
+impl MazeFile {
+  /// The maze `self` describes, along with every tag, crossing and lock dropped for sitting
+  /// outside it.
+  fn into_maze(self) -> Result<(Maze, Vec<ValidationError>), LoadError> {
+    let (width, height, depth) = (self.width, self.height, self.depth);
+    // The dimensions are untrusted, so the cell count must not overflow before it is checked.
+    let expected = (width as usize)
+      .checked_mul(height as usize)
+      .and_then(|cells| cells.checked_mul(depth as usize))
+      .ok_or_else(|| {
+        LoadError::Parse(format!("invalid maze dimensions {width}x{height}x{depth}"))
+      })?;
+    if expected == 0 || self.walls.len() != expected {
+      return Err(LoadError::GridMismatch {
+        expected,
+        found: self.walls.len(),
+      });
+    }
+    let mask = self
+      .mask
+      .map(|flags| {
+        let found = flags.len();
+        Mask::from_flags(self.width, self.height, self.depth, flags).ok_or_else(|| {
+          LoadError::Parse(format!("mask carries {found} flags for {expected} cells"))
+        })
+      })
+      .transpose()?;
+    let mut maze = Maze {
+      width: self.width,
+      height: self.height,
+      depth: self.depth,
+      walls: self.walls.into_iter().map(|w| w & ALL_WALLS).collect(),
+      seed: self.seed,
+      entrance: self.entrance.unwrap_or([0, 0, 0]),
+      exit: self
+        .exit
+        .unwrap_or_else(|| Maze::far_corner(self.width, self.height, self.depth)),
+      mask,
+      tags: HashMap::new(),
+      crossings: HashSet::new(),
+      locks: Vec::new(),
+      wrap: self.wrap,
+    };
+    let dropped = maze.restore_annotations(self.tags, self.crossings, self.locks);
+    Ok((maze, dropped))
+  }
+}
+
+impl MazeFile {
+  /// Parses a maze file document, migrating older versions to the current layout.
+  pub fn from_json(json: &str) -> Result<MazeFile, LoadError> {
+    let parse = |e: serde_json::Error| LoadError::Parse(e.to_string());
+    let version = serde_json::from_str::<VersionProbe>(json)
+      .map_err(parse)?
+      .version;
+    match version {
+      1 => serde_json::from_str::<MazeFileV1>(json)
+        .map_err(parse)?
+        .migrate(),
+      2 | MazeFile::CURRENT_VERSION => serde_json::from_str::<MazeFile>(json).map_err(parse),
+      version => Err(LoadError::UnsupportedVersion(version)),
+    }
+  }
+}
+
+impl Maze {
+  /// Serializes the maze as a current-version [`MazeFile`] document.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(&MazeFile::from(self)).expect("maze files always serialize")
+  }
+
+  /// Loads a [`MazeFile`] document, migrating older versions and dropping tags, crossings and
+  /// locks that sit outside the maze; [`Maze::from_json_with_warnings`] reports them.
+  pub fn from_json(json: &str) -> Result<Maze, LoadError> {
+    Maze::from_json_with_warnings(json).map(|(maze, _dropped)| maze)
+  }
+
+  /// [`Maze::from_json`], along with every tag, crossing and lock it dropped.
+  pub fn from_json_with_warnings(json: &str) -> Result<(Maze, Vec<ValidationError>), LoadError> {
+    MazeFile::from_json(json)?.into_maze()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::CellTag;
+
+  #[test]
+  fn round_trips_through_json() {
+    let maze = Maze::generate_with_seed((5, 4, 3), 9, Algorithm::Prim).unwrap();
+    assert_eq!(Maze::from_json(&maze.to_json()).unwrap(), maze);
+  }
+
+  #[test]
+  fn migrates_version_1() {
+    let maze = Maze::from_json(r#"{"version":1,"cells":[[[61,62]],[[45,46]]]}"#).unwrap();
+    assert_eq!(maze.dimensions(), (2, 1, 2));
+    assert_eq!(maze.wall_data(), &[61, 62, 45, 46]);
+    assert_eq!(maze.seed(), None);
+    assert_eq!(maze.exit(), [1, 0, 1]);
+  }
+
+  #[test]
+  fn rejects_a_future_version() {
+    assert_eq!(
+      Maze::from_json(r#"{"version":999}"#),
+      Err(LoadError::UnsupportedVersion(999))
+    );
+  }
+
+  #[test]
+  fn rejects_dimensions_that_overflow() {
+    let json = format!(
+      r#"{{"version":{},"width":{max},"height":{max},"depth":{max},"walls":[]}}"#,
+      MazeFile::CURRENT_VERSION,
+      max = u32::MAX
+    );
+    assert!(matches!(Maze::from_json(&json), Err(LoadError::Parse(_))));
+  }
+
+  fn saved_recipe() -> (Maze, String) {
+    let config = GenConfig {
+      braiding: 0.4,
+      ..GenConfig::new((6, 5, 2), Algorithm::Kruskal, 21)
+    };
+    let mut maze = Maze::generate(&config).unwrap();
+    maze.set_tag([2, 3, 1], CellTag::Spawn);
+    let file = MazeFile::generated(&maze, &config, &maze::Orthogonal6);
+    (maze, serde_json::to_string(&file).unwrap())
+  }
+
+  #[test]
+  fn regenerates_a_saved_maze() {
+    let (maze, json) = saved_recipe();
+    let file: MazeFile = serde_json::from_str(&json).unwrap();
+    assert_eq!(regenerate_from_file(&file).unwrap(), (maze, Vec::new()));
+  }
+
+  #[test]
+  fn refuses_to_regenerate_with_an_unknown_rng() {
+    let (_, json) = saved_recipe();
+    let json = json.replace(r#""rng":"chacha8""#, r#""rng":"pcg64""#);
+    let file: MazeFile = serde_json::from_str(&json).unwrap();
+    let e = regenerate_from_file(&file).unwrap_err();
+    assert!(
+      matches!(&e, LoadError::Irreproducible(reason) if reason.contains("pcg64")),
+      "{e}"
+    );
+  }
+
+  #[test]
+  fn loads_version_2_and_writes_the_current_version() {
+    let maze =
+      Maze::from_json(r#"{"version":2,"width":2,"height":1,"depth":1,"seed":4,"walls":[61,62]}"#)
+        .unwrap();
+    assert_eq!(maze.wall_data(), &[61, 62]);
+    assert_eq!((maze.seed(), maze.wrap()), (Some(4), WrapMode::None));
+
+    let mut wrapped = Maze::generate_with_seed((4, 3, 1), 2, Algorithm::Prim).unwrap();
+    wrapped.set_wrap(WrapMode::WrapX);
+    let json: serde_json::Value = serde_json::from_str(&wrapped.to_json()).unwrap();
+    assert_eq!(json["version"], 3);
+    assert_eq!(json["wrap"], "wrap_x");
+    assert_eq!(Maze::from_json(&json.to_string()).unwrap(), wrapped);
+  }
+
+  #[test]
+  fn refuses_to_regenerate_an_edited_maze() {
+    let (maze, json) = saved_recipe();
+    let mut file = MazeFile::from_json(&json).unwrap();
+    let cell = maze.index([0, 0, 0]);
+    file.walls[cell] ^= 1;
+    let e = regenerate_from_file(&file).unwrap_err();
+    assert!(matches!(e, LoadError::Irreproducible(_)), "{e}");
+  }
+}
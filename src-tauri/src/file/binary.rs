@@ -0,0 +1,342 @@
+//! Bit-packed binary layout for large mazes.
+//!
+//! All integers are little-endian, in this order:
+//!
+//! - magic `P3DM`, then the format version and a flags byte (bit 0: seed, bit 1: mask,
+//!   bit 2: tags, bit 3: crossings, bit 4: locks, bit 5: wrap);
+//! - width, height and depth as `u32`, then the seed as `u64` if flagged, then the wrap mode
+//!   as a byte (bit 0: `x`, bit 1: `y`) if flagged;
+//! - entrance and exit as three `u32` each;
+//! - three bits per cell in [`Maze::index`] order, set where its east, south and up faces are
+//!   walled;
+//! - if flagged, one bit per cell, set where the mask keeps it active;
+//! - if flagged, a `u32` tag count, then per tag its cell as three `u32`, a kind byte
+//!   (treasure `0`, trap `1`, spawn `2`, custom `3`) and, for custom tags, a `u32` length and
+//!   that many bytes of UTF-8;
+//! - if flagged, a `u32` crossing count, then each crossing cell as three `u32`;
+//! - if flagged, a `u32` lock count, then per lock its key and door cells as three `u32` each
+//!   and the door's face as its wall bit.
+//!
+//! Version 4 is the same layout without wrapping, version 3 without locks either, version 2
+//! without crossings and version 1 without tags.
+//! Bit streams are LSB-first and padded to a whole byte. Each shared face is stored once, by
+//! the cell west, north or below it, and faces on the grid boundary are always walled.
+
+use super::LoadError;
+use crate::maze::{
+  CellTag, Coord, Direction, Lock, Mask, Maze, TaggedCell, ValidationError, WrapMode,
+};
+
+const MAGIC: &[u8; 4] = b"P3DM";
+const VERSION: u8 = 5;
+const HAS_SEED: u8 = 1;
+const HAS_MASK: u8 = 2;
+const HAS_TAGS: u8 = 4;
+const HAS_CROSSINGS: u8 = 8;
+const HAS_LOCKS: u8 = 16;
+const HAS_WRAP: u8 = 32;
+
+/// Faces stored per cell, in bit order.
+const OWNED: [Direction; 3] = [Direction::East, Direction::South, Direction::Up];
+
+impl Maze {
+  /// Encodes the maze in the compact binary layout described in this module.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let flags = if self.seed.is_some() { HAS_SEED } else { 0 }
+      | if self.mask.is_some() { HAS_MASK } else { 0 }
+      | if self.tags.is_empty() { 0 } else { HAS_TAGS }
+      | if self.crossings.is_empty() {
+        0
+      } else {
+        HAS_CROSSINGS
+      }
+      | if self.locks.is_empty() { 0 } else { HAS_LOCKS }
+      | if self.wrap.is_none() { 0 } else { HAS_WRAP };
+    let mut bytes = Vec::with_capacity(64 + self.cell_count() / 2);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend([VERSION, flags]);
+    for value in [self.width, self.height, self.depth] {
+      bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    if let Some(seed) = self.seed {
+      bytes.extend_from_slice(&seed.to_le_bytes());
+    }
+    if !self.wrap.is_none() {
+      bytes.push(u8::from(self.wrap.wraps(0)) | u8::from(self.wrap.wraps(1)) << 1);
+    }
+    for value in self.entrance.into_iter().chain(self.exit) {
+      bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let walls =
+      (0..self.cell_count()).flat_map(|index| OWNED.map(|dir| self.walls[index] & dir.bit() != 0));
+    pack(walls, &mut bytes);
+    if let Some(mask) = &self.mask {
+      pack(mask.flags().iter().copied(), &mut bytes);
+    }
+    if !self.tags.is_empty() {
+      bytes.extend_from_slice(&(self.tags.len() as u32).to_le_bytes());
+      for TaggedCell { cell, tag } in self.tagged_cells() {
+        for value in cell {
+          bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let (kind, custom) = match &tag {
+          CellTag::Treasure => (0, None),
+          CellTag::Trap => (1, None),
+          CellTag::Spawn => (2, None),
+          CellTag::Custom(name) => (3, Some(name.as_bytes())),
+        };
+        bytes.push(kind);
+        if let Some(name) = custom {
+          bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+          bytes.extend_from_slice(name);
+        }
+      }
+    }
+    if !self.crossings.is_empty() {
+      bytes.extend_from_slice(&(self.crossings.len() as u32).to_le_bytes());
+      for value in self.crossings().into_iter().flatten() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+      }
+    }
+    if !self.locks.is_empty() {
+      bytes.extend_from_slice(&(self.locks.len() as u32).to_le_bytes());
+      for lock in &self.locks {
+        for value in lock.key.into_iter().chain(lock.door) {
+          bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.push(lock.dir.bit());
+      }
+    }
+    bytes
+  }
+
+  /// Decodes a maze written by [`Maze::to_bytes`], dropping tags, crossings and locks that
+  /// sit outside it; [`Maze::from_bytes_with_warnings`] reports them.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Maze, LoadError> {
+    Maze::from_bytes_with_warnings(bytes).map(|(maze, _dropped)| maze)
+  }
+
+  /// [`Maze::from_bytes`], along with every tag, crossing and lock it dropped.
+  pub fn from_bytes_with_warnings(bytes: &[u8]) -> Result<(Maze, Vec<ValidationError>), LoadError> {
+    let mut reader = Reader { bytes };
+    if reader.take(4)? != MAGIC {
+      return Err(LoadError::Parse("not a binary maze file".to_owned()));
+    }
+    let [version, flags] = [reader.u8()?, reader.u8()?];
+    // Each version added one section, flagged by the next bit.
+    let known = match version {
+      1 => HAS_SEED | HAS_MASK,
+      2 => HAS_SEED | HAS_MASK | HAS_TAGS,
+      3 => HAS_SEED | HAS_MASK | HAS_TAGS | HAS_CROSSINGS,
+      4 => HAS_SEED | HAS_MASK | HAS_TAGS | HAS_CROSSINGS | HAS_LOCKS,
+      _ => HAS_SEED | HAS_MASK | HAS_TAGS | HAS_CROSSINGS | HAS_LOCKS | HAS_WRAP,
+    };
+    if !(1..=VERSION).contains(&version) || flags & !known != 0 {
+      return Err(LoadError::UnsupportedVersion(version.into()));
+    }
+    let (width, height, depth) = (reader.u32()?, reader.u32()?, reader.u32()?);
+    let seed = if flags & HAS_SEED != 0 {
+      Some(reader.u64()?)
+    } else {
+      None
+    };
+    let wrap = if flags & HAS_WRAP != 0 {
+      match reader.u8()? {
+        1 => WrapMode::WrapX,
+        2 => WrapMode::WrapY,
+        3 => WrapMode::WrapXY,
+        bits => return Err(LoadError::Parse(format!("unknown wrap mode {bits}"))),
+      }
+    } else {
+      WrapMode::None
+    };
+    let entrance = reader.coord()?;
+    let exit = reader.coord()?;
+
+    // The header is untrusted, so the cell count must not overflow before it is checked.
+    let cells = (width as usize)
+      .checked_mul(height as usize)
+      .and_then(|cells| cells.checked_mul(depth as usize))
+      .filter(|&cells| cells > 0 && cells <= usize::MAX / 8)
+      .ok_or_else(|| {
+        LoadError::Parse(format!("invalid maze dimensions {width}x{height}x{depth}"))
+      })?;
+    let wall_bytes = (cells * OWNED.len()).div_ceil(8);
+    let mask_bytes = if flags & HAS_MASK != 0 {
+      cells.div_ceil(8)
+    } else {
+      0
+    };
+    if reader.bytes.len() < wall_bytes + mask_bytes {
+      return Err(LoadError::Parse(format!(
+        "{cells} cells need {} bytes of grid data but {} remain",
+        wall_bytes + mask_bytes,
+        reader.bytes.len()
+      )));
+    }
+
+    let mut maze = Maze::new(width, height, depth);
+    maze.seed = seed;
+    maze.entrance = entrance;
+    maze.exit = exit;
+    maze.wrap = wrap;
+    let walls = unpack(reader.take(wall_bytes)?);
+    for index in 0..cells {
+      let cell = maze.coord(index);
+      for (slot, dir) in OWNED.into_iter().enumerate() {
+        if walls[index * OWNED.len() + slot] {
+          continue;
+        }
+        let next = maze.adjacent(cell, dir).ok_or_else(|| {
+          LoadError::Parse(format!("open {dir:?} face on the boundary at {cell:?}"))
+        })?;
+        let next = maze.index(next);
+        maze.walls[index] &= !dir.bit();
+        maze.walls[next] &= !dir.opposite().bit();
+      }
+    }
+    if mask_bytes > 0 {
+      let mut active = unpack(reader.take(mask_bytes)?);
+      active.truncate(cells);
+      maze.mask = Mask::from_flags(width, height, depth, active);
+    }
+    let mut tagged = Vec::new();
+    if flags & HAS_TAGS != 0 {
+      let count = reader.u32()?;
+      for _ in 0..count {
+        let cell = reader.coord()?;
+        let tag = match reader.u8()? {
+          0 => CellTag::Treasure,
+          1 => CellTag::Trap,
+          2 => CellTag::Spawn,
+          3 => {
+            let len = reader.u32()? as usize;
+            let name = std::str::from_utf8(reader.take(len)?)
+              .map_err(|_| LoadError::Parse("custom tag is not valid UTF-8".to_owned()))?;
+            CellTag::Custom(name.to_owned())
+          }
+          kind => return Err(LoadError::Parse(format!("unknown tag kind {kind}"))),
+        };
+        tagged.push(TaggedCell { cell, tag });
+      }
+    }
+    let mut crossings = Vec::new();
+    if flags & HAS_CROSSINGS != 0 {
+      let count = reader.u32()?;
+      for _ in 0..count {
+        crossings.push(reader.coord()?);
+      }
+    }
+    let mut locks = Vec::new();
+    if flags & HAS_LOCKS != 0 {
+      let count = reader.u32()?;
+      for _ in 0..count {
+        let (key, door, bit) = (reader.coord()?, reader.coord()?, reader.u8()?);
+        let dir = Direction::ALL
+          .into_iter()
+          .find(|dir| dir.bit() == bit)
+          .ok_or_else(|| LoadError::Parse(format!("unknown lock face {bit}")))?;
+        locks.push(Lock { key, door, dir });
+      }
+    }
+    if !reader.bytes.is_empty() {
+      return Err(LoadError::Parse(format!(
+        "{} unexpected bytes after the maze",
+        reader.bytes.len()
+      )));
+    }
+    let dropped = maze.restore_annotations(tagged, crossings, locks);
+    Ok((maze, dropped))
+  }
+}
+
+/// Appends `bits` LSB-first, padding the last byte with zeros.
+fn pack(bits: impl Iterator<Item = bool>, out: &mut Vec<u8>) {
+  let mut byte = 0u8;
+  let mut filled = 0;
+  for bit in bits {
+    byte |= u8::from(bit) << filled;
+    filled += 1;
+    if filled == 8 {
+      out.push(byte);
+      (byte, filled) = (0, 0);
+    }
+  }
+  if filled > 0 {
+    out.push(byte);
+  }
+}
+
+fn unpack(bytes: &[u8]) -> Vec<bool> {
+  bytes
+    .iter()
+    .flat_map(|&byte| (0..8).map(move |bit| byte >> bit & 1 == 1))
+    .collect()
+}
+
+/// Cursor over the unread part of a binary maze.
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, len: usize) -> Result<&'a [u8], LoadError> {
+    if self.bytes.len() < len {
+      return Err(LoadError::Parse("binary maze file is truncated".to_owned()));
+    }
+    let (head, rest) = self.bytes.split_at(len);
+    self.bytes = rest;
+    Ok(head)
+  }
+
+  fn u8(&mut self) -> Result<u8, LoadError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u32(&mut self) -> Result<u32, LoadError> {
+    let bytes = self.take(4)?.try_into().expect("took 4 bytes");
+    Ok(u32::from_le_bytes(bytes))
+  }
+
+  fn u64(&mut self) -> Result<u64, LoadError> {
+    let bytes = self.take(8)?.try_into().expect("took 8 bytes");
+    Ok(u64::from_le_bytes(bytes))
+  }
+
+  fn coord(&mut self) -> Result<Coord, LoadError> {
+    Ok([self.u32()?, self.u32()?, self.u32()?])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+
+  #[test]
+  fn round_trips_at_several_sizes() {
+    for (dims, seed) in [
+      ((1, 1, 1), 1),
+      ((2, 3, 1), 2),
+      ((7, 5, 3), 3),
+      ((16, 1, 9), 4),
+    ] {
+      let maze = Maze::generate_with_seed(dims, seed, Algorithm::Kruskal).unwrap();
+      assert_eq!(
+        Maze::from_bytes(&maze.to_bytes()).unwrap(),
+        maze,
+        "{dims:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn a_large_maze_fits_the_byte_budget() {
+    let maze = Maze::generate_with_seed((100, 100, 5), 5, Algorithm::Prim).unwrap();
+    let bytes = maze.to_bytes().len();
+    // Three owned faces per cell come to 18 750 bytes; the header is small.
+    assert!(bytes < 19_000, "{bytes} bytes");
+    assert!(bytes * 5 <= maze.to_json().len());
+  }
+}
@@ -0,0 +1,1514 @@
+//! Maze carving algorithms.
+//!
+//! Every algorithm starts from a fully walled [`Maze`] and carves a spanning tree through the
+//! faces it may open: all six by default, so levels connect to each other as well as
+//! internally, or only the four planar ones when a [`VerticalConfig`] adds stairs afterwards.
+//! The `_in` entry points restrict carving to the faces a [`Topology`] allows, and
+//! [`MazeBuilder`] assembles the options one setter at a time.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::{Coord, Direction, Mask, Maze, Orthogonal6, Topology, WrapMode};
+use crate::solver;
+
+mod braid;
+mod builder;
+mod locks;
+mod region;
+mod symmetry;
+
+pub use braid::{braid, DifficultyGradient};
+pub(crate) use builder::fresh_seed;
+pub use builder::{BuildError, MazeBuilder};
+pub use locks::LockConfig;
+pub use symmetry::Symmetry;
+
+/// Carving algorithm, named by its snake_case id on the frontend.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+  RecursiveBacktracker,
+  Prim,
+  Kruskal,
+  /// Random walks like the backtracker, but rescans for a new start instead of backing up.
+  HuntAndKill,
+  /// Unbiased random walk over the whole grid; slow to finish, so mazes above
+  /// [`ALDOUS_BRODER_MAX_CELLS`] are refused.
+  AldousBroder,
+  /// Splits the grid with walls that each keep one gap, for long straight walls and a roomier
+  /// look.
+  RecursiveDivision,
+}
+
+impl Algorithm {
+  pub const ALL: [Algorithm; 6] = [
+    Algorithm::RecursiveBacktracker,
+    Algorithm::Prim,
+    Algorithm::Kruskal,
+    Algorithm::HuntAndKill,
+    Algorithm::AldousBroder,
+    Algorithm::RecursiveDivision,
+  ];
+
+  pub const fn id(self) -> &'static str {
+    match self {
+      Algorithm::RecursiveBacktracker => "recursive_backtracker",
+      Algorithm::Prim => "prim",
+      Algorithm::Kruskal => "kruskal",
+      Algorithm::HuntAndKill => "hunt_and_kill",
+      Algorithm::AldousBroder => "aldous_broder",
+      Algorithm::RecursiveDivision => "recursive_division",
+    }
+  }
+
+  /// Name to show in menus.
+  pub const fn name(self) -> &'static str {
+    match self {
+      Algorithm::RecursiveBacktracker => "Recursive backtracker",
+      Algorithm::Prim => "Prim's",
+      Algorithm::Kruskal => "Kruskal's",
+      Algorithm::HuntAndKill => "Hunt and kill",
+      Algorithm::AldousBroder => "Aldous-Broder",
+      Algorithm::RecursiveDivision => "Recursive division",
+    }
+  }
+
+  /// Whether [`GenConfig::bias`] changes what it carves.
+  pub const fn supports_bias(self) -> bool {
+    matches!(self, Algorithm::RecursiveBacktracker)
+  }
+
+  /// Whether it carves a perfect maze, with exactly one path between any two cells, until
+  /// braiding adds loops.
+  pub const fn is_perfect(self) -> bool {
+    match self {
+      Algorithm::RecursiveBacktracker
+      | Algorithm::Prim
+      | Algorithm::Kruskal
+      | Algorithm::HuntAndKill
+      | Algorithm::AldousBroder
+      | Algorithm::RecursiveDivision => true,
+    }
+  }
+}
+
+/// Largest grid [`Algorithm::AldousBroder`] will carve. Its walk needs on the order of
+/// `n log² n` steps to reach every cell, which is already seconds at this size.
+pub const ALDOUS_BRODER_MAX_CELLS: usize = 250_000;
+
+impl FromStr for Algorithm {
+  type Err = GenerateError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Algorithm::ALL
+      .into_iter()
+      .find(|algorithm| algorithm.id() == s)
+      .ok_or_else(|| GenerateError::UnknownAlgorithm(s.to_owned()))
+  }
+}
+
+/// Random number generator behind a seed, recorded so a seed keeps meaning the same maze.
+///
+/// A future build may switch generators; old seeds then still name the one they were drawn
+/// from, and a build without it says so instead of carving something else.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RngKind {
+  /// ChaCha8 from `rand_chacha` 0.3, sampled through `rand` 0.8.
+  #[default]
+  #[serde(rename = "chacha8")]
+  ChaCha8,
+  /// A generator this build does not know, by the id it was saved under.
+  #[serde(untagged)]
+  Unknown(String),
+}
+
+impl RngKind {
+  /// The generator new mazes use.
+  pub const CURRENT: RngKind = RngKind::ChaCha8;
+
+  /// Seeds this generator, or fails if this build cannot reproduce it.
+  fn seed(&self, seed: u64) -> Result<ChaCha8Rng, GenerateError> {
+    match self {
+      RngKind::ChaCha8 => Ok(ChaCha8Rng::seed_from_u64(seed)),
+      RngKind::Unknown(id) => Err(GenerateError::UnsupportedRng(id.clone())),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenerateError {
+  /// At least one axis was zero.
+  ZeroDimension { width: u32, height: u32, depth: u32 },
+  /// The algorithm name did not match any [`Algorithm::id`].
+  UnknownAlgorithm(String),
+  /// Levels are carved separately but no stairs were requested to join them.
+  NoStairs { depth: u32 },
+  /// The mask's size differs from the requested dimensions.
+  MaskMismatch {
+    mask: (u32, u32, u32),
+    dims: (u32, u32, u32),
+  },
+  /// The mask leaves no active cell.
+  EmptyMask,
+  /// The mask splits the active cells into islands; lists every region the entrance cannot
+  /// reach.
+  Disconnected { unreachable: Vec<Vec<Coord>> },
+  /// The cancel flag passed to [`Maze::generate_with_progress`] was raised.
+  Cancelled,
+  /// A [`PortalStrategy::Explicit`] cell is outside the maze or masked off.
+  BadPortal(Coord),
+  /// The box passed to [`Maze::regenerate_region`] is inverted or leaves the grid.
+  BadRegion { min: Coord, max: Coord },
+  /// The maze has more cells than `algorithm` handles in reasonable time.
+  TooLarge {
+    algorithm: Algorithm,
+    cells: usize,
+    limit: usize,
+  },
+  /// [`GenConfig::rng`] names a generator this build does not have.
+  UnsupportedRng(String),
+  /// The dimensions ask for more cells than [`GenConfig::limits`] allows.
+  Oversized { dims: (u32, u32, u32), limit: u64 },
+  /// [`GenConfig::symmetry`] is set but the mask does not have that symmetry.
+  AsymmetricMask(Symmetry),
+  /// [`GenConfig::locks`] asks for more doors than the solution path has room for.
+  TooManyKeys { keys: u32, room: u32 },
+}
+
+impl fmt::Display for GenerateError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GenerateError::ZeroDimension {
+        width,
+        height,
+        depth,
+      } => write!(
+        f,
+        "invalid maze dimensions {width}x{height}x{depth}: every axis must be at least 1"
+      ),
+      GenerateError::UnknownAlgorithm(name) => {
+        let known: Vec<_> = Algorithm::ALL.iter().map(|a| a.id()).collect();
+        write!(
+          f,
+          "unknown algorithm '{name}' (expected one of: {})",
+          known.join(", ")
+        )
+      }
+      GenerateError::NoStairs { depth } => write!(
+        f,
+        "stairs_per_level must be at least 1 to connect {depth} levels"
+      ),
+      GenerateError::MaskMismatch { mask, dims } => write!(
+        f,
+        "mask is {}x{}x{} but the maze is {}x{}x{}",
+        mask.0, mask.1, mask.2, dims.0, dims.1, dims.2
+      ),
+      GenerateError::EmptyMask => write!(f, "mask has no active cells"),
+      GenerateError::Disconnected { unreachable } => {
+        let starts: Vec<String> = unreachable
+          .iter()
+          .map(|region| format!("{:?} ({} cells)", region[0], region.len()))
+          .collect();
+        write!(
+          f,
+          "mask leaves {} region(s) unreachable from the entrance: {}",
+          unreachable.len(),
+          starts.join(", ")
+        )
+      }
+      GenerateError::Cancelled => write!(f, "generation was cancelled"),
+      GenerateError::BadPortal(cell) => {
+        write!(f, "portal {cell:?} is outside the maze or masked off")
+      }
+      GenerateError::BadRegion { min, max } => {
+        write!(f, "region {min:?}..={max:?} is not a box inside the maze")
+      }
+      GenerateError::TooLarge {
+        algorithm,
+        cells,
+        limit,
+      } => write!(
+        f,
+        "{} is limited to {limit} cells but the maze has {cells}",
+        algorithm.id()
+      ),
+      GenerateError::UnsupportedRng(id) => write!(
+        f,
+        "seed was drawn from the '{id}' generator, which this build cannot reproduce"
+      ),
+      GenerateError::Oversized { dims, limit } => {
+        // Three u32 factors always fit in a u128.
+        let cells = u128::from(dims.0) * u128::from(dims.1) * u128::from(dims.2);
+        write!(f, "maze too large: {cells} cells exceeds limit {limit}")
+      }
+      GenerateError::AsymmetricMask(symmetry) => write!(
+        f,
+        "mask does not have the requested {} symmetry",
+        symmetry.id()
+      ),
+      GenerateError::TooManyKeys { keys, room } => write!(
+        f,
+        "cannot lock {keys} doors: the path to the exit only has room for {room}"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Stairs layout used when levels are carved separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerticalConfig {
+  /// Stairs carved between each level and the one above it.
+  pub stairs_per_level: u32,
+  /// Minimum Manhattan distance, within a level, between any two stairs touching it.
+  pub min_spacing: u32,
+}
+
+/// Preferred corridor direction for [`Algorithm::RecursiveBacktracker`].
+///
+/// The strength (clamped to `0.0..=1.0`) is the chance that each step picks among the
+/// unvisited neighbours along the favoured axis, when there are any, instead of among all of
+/// them. Other algorithms ignore the bias.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bias {
+  #[default]
+  None,
+  /// Long east-west corridors.
+  Horizontal(f32),
+  /// Long north-south corridors, along `y` on the top-down view.
+  Vertical(f32),
+}
+
+impl Bias {
+  /// Favoured directions and the chance of keeping to them.
+  fn axis(self) -> Option<([Direction; 2], f32)> {
+    match self {
+      Bias::None => None,
+      Bias::Horizontal(strength) => Some(([Direction::East, Direction::West], strength)),
+      Bias::Vertical(strength) => Some(([Direction::South, Direction::North], strength)),
+    }
+  }
+}
+
+/// Where [`Maze::generate`] puts the entrance and exit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortalStrategy {
+  /// `[0, 0, 0]` and the far corner, or the first and last active cells of a mask.
+  #[default]
+  OppositeCorners,
+  /// Two distinct active cells drawn from the maze's RNG.
+  Random,
+  /// Both ends of the longest shortest path, found by double BFS. Exact for perfect mazes;
+  /// after [`braid`] it may fall slightly short.
+  FarthestPair,
+  /// `[entrance, exit]` as given.
+  Explicit([Coord; 2]),
+}
+
+/// Largest maze generated unless [`GenerationLimits`] says otherwise; the walls alone take one
+/// byte per cell, and the frontend receives them as JSON.
+pub const DEFAULT_MAX_CELLS: u64 = 1 << 24;
+
+/// Bounds checked before anything is allocated, so a hostile request cannot exhaust memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationLimits {
+  pub max_cells: u64,
+}
+
+impl Default for GenerationLimits {
+  fn default() -> Self {
+    GenerationLimits {
+      max_cells: DEFAULT_MAX_CELLS,
+    }
+  }
+}
+
+impl GenerationLimits {
+  /// Cell count of `dims`, or [`GenerateError::Oversized`] if it exceeds `max_cells` or does
+  /// not even fit in a `u64`.
+  pub fn check(&self, dims: (u32, u32, u32)) -> Result<u64, GenerateError> {
+    u64::from(dims.0)
+      .checked_mul(u64::from(dims.1))
+      .and_then(|cells| cells.checked_mul(u64::from(dims.2)))
+      .filter(|&cells| cells <= self.max_cells)
+      .ok_or(GenerateError::Oversized {
+        dims,
+        limit: self.max_cells,
+      })
+  }
+}
+
+/// Everything that determines a generated maze.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenConfig {
+  pub dims: (u32, u32, u32),
+  pub algorithm: Algorithm,
+  pub seed: u64,
+  pub rng: RngKind,
+  /// Carve every level as its own 2D maze and join them with explicit stairs instead of
+  /// letting the algorithm wander between levels.
+  pub vertical: Option<VerticalConfig>,
+  /// Cells left out of the maze; must match `dims`.
+  pub mask: Option<Mask>,
+  /// Fraction of dead ends opened into loops afterwards, see [`braid`]; `0.0` keeps the maze
+  /// perfect.
+  pub braiding: f32,
+  /// More braiding near the entrance than near the exit, or the other way round, on top of
+  /// `braiding`; measured once the portals are in place.
+  pub gradient: Option<DifficultyGradient>,
+  pub portals: PortalStrategy,
+  pub bias: Bias,
+  /// Let the recursive backtracker tunnel under straight corridors, leaving weave crossings;
+  /// other algorithms ignore it.
+  pub weave: bool,
+  /// Carve half the maze and mirror it; the mask must already be symmetric.
+  pub symmetry: Option<Symmetry>,
+  /// Lock doors on the way to the exit behind keys, once the portals are in place.
+  pub locks: Option<LockConfig>,
+  /// Let passages run off one edge and back in at the opposite one. Recursive division keeps
+  /// to the plain grid, though braiding may still open wrapping faces.
+  pub wrap: WrapMode,
+  /// Never changes the maze, only whether it is generated at all.
+  pub limits: GenerationLimits,
+}
+
+impl GenConfig {
+  pub fn new(dims: (u32, u32, u32), algorithm: Algorithm, seed: u64) -> GenConfig {
+    GenConfig {
+      dims,
+      algorithm,
+      seed,
+      rng: RngKind::CURRENT,
+      vertical: None,
+      mask: None,
+      braiding: 0.0,
+      gradient: None,
+      portals: PortalStrategy::OppositeCorners,
+      bias: Bias::None,
+      weave: false,
+      symmetry: None,
+      locks: None,
+      wrap: WrapMode::None,
+      limits: GenerationLimits::default(),
+    }
+  }
+}
+
+/// How far a [`Maze::generate_with_progress`] run has got.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+  /// Passages opened so far, capped at `total`.
+  pub carved: u64,
+  /// Passages a spanning tree over the active cells needs.
+  pub total: u64,
+}
+
+/// Counts carved passages, reports every 1% of the total and checks for cancellation.
+struct Tracker<'a> {
+  progress: Progress,
+  step: u64,
+  cancel: Option<&'a AtomicBool>,
+  report: &'a mut dyn FnMut(Progress),
+}
+
+impl Tracker<'_> {
+  fn carve(&mut self, maze: &mut Maze, cell: Coord, dir: Direction) -> Result<(), GenerateError> {
+    maze.carve(cell, dir);
+    let progress = &mut self.progress;
+    if progress.carved == progress.total {
+      // Stairs beyond the spanning tree; nothing left to report.
+      return Ok(());
+    }
+    progress.carved += 1;
+    if progress.carved.is_multiple_of(self.step) || progress.carved == progress.total {
+      (self.report)(*progress);
+      if self
+        .cancel
+        .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+      {
+        return Err(GenerateError::Cancelled);
+      }
+    }
+    Ok(())
+  }
+}
+
+impl Maze {
+  /// Generates the maze described by `config`.
+  ///
+  /// The same config always carves the same maze, on any machine.
+  pub fn generate(config: &GenConfig) -> Result<Maze, GenerateError> {
+    Maze::generate_tracked(config, &Orthogonal6, None, &mut |_| {})
+  }
+
+  /// [`Maze::generate`] carving only the faces `topology` allows.
+  ///
+  /// Without vertical moves every level becomes its own maze: `vertical` places no stairs and
+  /// the levels stay apart, matching what the solver can walk under the same topology.
+  pub fn generate_in(config: &GenConfig, topology: &dyn Topology) -> Result<Maze, GenerateError> {
+    Maze::generate_tracked(config, topology, None, &mut |_| {})
+  }
+
+  /// [`Maze::generate`] that calls `on_progress` after every 1% of the carving and stops with
+  /// [`GenerateError::Cancelled`] soon after `cancel` is raised.
+  ///
+  /// Progress never changes the result: the same config still carves the same maze.
+  pub fn generate_with_progress(
+    config: &GenConfig,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(Progress),
+  ) -> Result<Maze, GenerateError> {
+    Maze::generate_with_progress_in(config, &Orthogonal6, cancel, on_progress)
+  }
+
+  /// [`Maze::generate_with_progress`] carving only the faces `topology` allows, as in
+  /// [`Maze::generate_in`].
+  pub fn generate_with_progress_in(
+    config: &GenConfig,
+    topology: &dyn Topology,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(Progress),
+  ) -> Result<Maze, GenerateError> {
+    Maze::generate_tracked(config, topology, Some(cancel), &mut on_progress)
+  }
+
+  fn generate_tracked(
+    config: &GenConfig,
+    topology: &dyn Topology,
+    cancel: Option<&AtomicBool>,
+    report: &mut dyn FnMut(Progress),
+  ) -> Result<Maze, GenerateError> {
+    let (width, height, depth) = config.dims;
+    if width == 0 || height == 0 || depth == 0 {
+      return Err(GenerateError::ZeroDimension {
+        width,
+        height,
+        depth,
+      });
+    }
+    config.limits.check(config.dims)?;
+    let mut rng = config.rng.seed(config.seed)?;
+    let stairs = config.vertical.filter(|_| topology.is_vertical());
+    if let Some(vertical) = stairs {
+      if depth > 1 && vertical.stairs_per_level == 0 {
+        return Err(GenerateError::NoStairs { depth });
+      }
+    }
+
+    let mut maze = Maze::new(width, height, depth);
+    maze.wrap = config.wrap;
+    if let Some(mask) = &config.mask {
+      if mask.dimensions() != config.dims {
+        return Err(GenerateError::MaskMismatch {
+          mask: mask.dimensions(),
+          dims: config.dims,
+        });
+      }
+      maze.mask = Some(mask.clone());
+      let mut active = (0..maze.cell_count()).filter(|&index| mask.flags()[index]);
+      let first = active.next().ok_or(GenerateError::EmptyMask)?;
+      maze.entrance = maze.coord(first);
+      maze.exit = maze.coord(active.next_back().unwrap_or(first));
+      let mut regions = regions(&maze);
+      if regions.len() > 1 {
+        regions.retain(|region| !region.contains(&maze.entrance));
+        return Err(GenerateError::Disconnected {
+          unreachable: regions,
+        });
+      }
+    }
+
+    if let PortalStrategy::Explicit(cells) = config.portals {
+      if let Some(&cell) = cells.iter().find(|&&cell| !maze.is_active(cell)) {
+        return Err(GenerateError::BadPortal(cell));
+      }
+    }
+
+    let active = config
+      .mask
+      .as_ref()
+      .map_or(maze.cell_count(), Mask::active_count) as u64;
+    // Without stairs each level is a tree of its own.
+    let joined = stairs.is_some() || config.vertical.is_none() && topology.is_vertical();
+    let trees = if joined {
+      1
+    } else {
+      (0..depth)
+        .filter(|&z| (0..width * height).any(|i| maze.is_active([i % width, i / width, z])))
+        .count() as u64
+    };
+    let total = active - trees;
+    let mut tracker = Tracker {
+      progress: Progress { carved: 0, total },
+      step: (total / 100).max(1),
+      cancel,
+      report,
+    };
+    let planar: Vec<Direction> = topology
+      .directions()
+      .iter()
+      .copied()
+      .filter(|dir| !matches!(dir, Direction::Up | Direction::Down))
+      .collect();
+    let dirs: &[Direction] = if config.vertical.is_some() {
+      &planar
+    } else {
+      topology.directions()
+    };
+    match config.symmetry {
+      Some(symmetry) => {
+        symmetry::carve_symmetric(&mut maze, config, symmetry, dirs, &mut rng, &mut tracker)?
+      }
+      None => carve(&mut maze, config, dirs, &mut rng, &mut tracker)?,
+    }
+    if let Some(vertical) = stairs {
+      place_stairs(&mut maze, vertical, &mut rng, &mut tracker)?;
+    }
+    if config.braiding > 0.0 {
+      braid::braid_through(&mut maze, config.braiding, dirs, &mut rng);
+    }
+    if let Some(symmetry) = config.symmetry {
+      symmetry::symmetrize(&mut maze, symmetry);
+    }
+    place_portals(&mut maze, config.portals, topology, &mut rng);
+    if let Some(gradient) = config.gradient {
+      braid::braid_graded(&mut maze, gradient, dirs, topology, &mut rng);
+      if let Some(symmetry) = config.symmetry {
+        symmetry::symmetrize(&mut maze, symmetry);
+      }
+    }
+    if let Some(lock_config) = config.locks {
+      locks::place_locks(&mut maze, lock_config, topology, &mut rng)?;
+    }
+    maze.seed = Some(config.seed);
+    Ok(maze)
+  }
+
+  /// Generates a maze for `seed` with default options; shorthand for [`Maze::generate`].
+  pub fn generate_with_seed(
+    dims: (u32, u32, u32),
+    seed: u64,
+    algorithm: Algorithm,
+  ) -> Result<Maze, GenerateError> {
+    Maze::generate(&GenConfig::new(dims, algorithm, seed))
+  }
+
+  /// Generates a maze covering only the active cells of `mask`.
+  ///
+  /// Entrance and exit move to the first and last active cells in index order. Fails with
+  /// [`GenerateError::Disconnected`] if the active cells do not form a single region.
+  pub fn generate_masked(
+    mask: &Mask,
+    algorithm: Algorithm,
+    seed: u64,
+  ) -> Result<Maze, GenerateError> {
+    Maze::generate(&GenConfig {
+      mask: Some(mask.clone()),
+      ..GenConfig::new(mask.dimensions(), algorithm, seed)
+    })
+  }
+}
+
+/// Groups the active cells into regions connected through any face, walls ignored.
+fn regions(maze: &Maze) -> Vec<Vec<Coord>> {
+  let mut seen = vec![false; maze.cell_count()];
+  let mut regions = Vec::new();
+  for index in 0..maze.cell_count() {
+    let start = maze.coord(index);
+    if seen[index] || !maze.is_active(start) {
+      continue;
+    }
+    seen[index] = true;
+    let mut region = vec![start];
+    let mut cursor = 0;
+    while let Some(&cell) = region.get(cursor) {
+      cursor += 1;
+      for dir in Direction::ALL {
+        if let Some(next) = maze.neighbor(cell, dir) {
+          if !std::mem::replace(&mut seen[maze.index(next)], true) {
+            region.push(next);
+          }
+        }
+      }
+    }
+    regions.push(region);
+  }
+  regions
+}
+
+/// Moves the entrance and exit of a carved maze according to `portals`.
+fn place_portals<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  portals: PortalStrategy,
+  topology: &dyn Topology,
+  rng: &mut R,
+) {
+  let [entrance, exit] = match portals {
+    // Already set when the maze was created.
+    PortalStrategy::OppositeCorners => return,
+    PortalStrategy::Random => {
+      let active: Vec<Coord> = (0..maze.cell_count())
+        .map(|index| maze.coord(index))
+        .filter(|&cell| maze.is_active(cell))
+        .collect();
+      let picked: Vec<Coord> = active.choose_multiple(rng, 2).copied().collect();
+      // A single-cell maze has nowhere else to put the exit.
+      [picked[0], *picked.last().unwrap_or(&picked[0])]
+    }
+    PortalStrategy::FarthestPair => {
+      let first = solver::farthest(
+        maze,
+        &solver::distance_field_in(maze, maze.entrance, topology),
+      );
+      let second = solver::farthest(maze, &solver::distance_field_in(maze, first, topology));
+      [first, second]
+    }
+    PortalStrategy::Explicit(cells) => cells,
+  };
+  maze.entrance = entrance;
+  maze.exit = exit;
+}
+
+/// Visited flags with the masked-off cells already marked, so walks never start there.
+fn unvisited(maze: &Maze) -> Vec<bool> {
+  (0..maze.cell_count())
+    .map(|index| !maze.is_active(maze.coord(index)))
+    .collect()
+}
+
+/// Carves a spanning forest of `maze` through the faces in `dirs`.
+///
+/// With all six directions this is a single tree; with only the planar ones it is one tree per
+/// level.
+fn carve<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  config: &GenConfig,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  match config.algorithm {
+    Algorithm::RecursiveBacktracker => {
+      recursive_backtracker(maze, dirs, config.bias, config.weave, rng, tracker)
+    }
+    Algorithm::Prim => prim(maze, dirs, rng, tracker),
+    Algorithm::Kruskal => kruskal(maze, dirs, rng, tracker),
+    Algorithm::HuntAndKill => hunt_and_kill(maze, dirs, rng, tracker),
+    Algorithm::AldousBroder => aldous_broder(maze, dirs, rng, tracker),
+    Algorithm::RecursiveDivision => recursive_division(maze, dirs, rng, tracker),
+  }
+}
+
+/// Depth-first walk that backs up whenever it runs out of unvisited neighbours.
+///
+/// With `weave`, the walk may also pass under a visited straight corridor to the unvisited
+/// cell beyond it, turning the corridor cell into a crossing.
+fn recursive_backtracker<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  bias: Bias,
+  weave: bool,
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  let mut visited = unvisited(maze);
+  let first = rng.gen_range(0..maze.cell_count());
+  // After the random start, restart in every region the walk could not reach, such as the
+  // other levels when only planar faces may be carved.
+  for start in std::iter::once(first).chain(0..maze.cell_count()) {
+    if visited[start] {
+      continue;
+    }
+    visited[start] = true;
+    let mut stack = vec![maze.coord(start)];
+
+    while let Some(&current) = stack.last() {
+      let mut options: Vec<(Direction, Coord)> = dirs
+        .iter()
+        .filter_map(|&dir| maze.neighbor(current, dir).map(|next| (dir, next)))
+        .filter(|&(_, next)| !visited[maze.index(next)])
+        .collect();
+      if weave {
+        options.extend(dirs.iter().filter_map(|&dir| {
+          let beyond = tunnel(maze, current, dir)?;
+          (!visited[maze.index(beyond)]).then_some((dir, beyond))
+        }));
+      }
+
+      match choose_biased(&options, bias, rng) {
+        Some((dir, next)) => {
+          // A tunnel lands two cells away, under the corridor in between.
+          let mut from = current;
+          if maze.adjacent(current, dir) != Some(next) {
+            from = maze
+              .carve(current, dir)
+              .expect("tunnels pass an active cell");
+            maze.crossings.insert(from);
+          }
+          tracker.carve(maze, from, dir)?;
+          visited[maze.index(next)] = true;
+          stack.push(next);
+        }
+        None => {
+          stack.pop();
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// The cell two steps along `dir` from `cell` if the one in between is a plain straight
+/// corridor across `dir` that a weave can pass under.
+fn tunnel(maze: &Maze, cell: Coord, dir: Direction) -> Option<Coord> {
+  let [across, back] = match dir {
+    Direction::East | Direction::West => [Direction::South, Direction::North],
+    Direction::South | Direction::North => [Direction::East, Direction::West],
+    Direction::Up | Direction::Down => return None,
+  };
+  let under = maze.neighbor(cell, dir)?;
+  let straight = Direction::ALL
+    .into_iter()
+    .all(|side| maze.has_wall(under, side) != (side == across || side == back));
+  let beyond = maze.neighbor(under, dir)?;
+  (straight && !maze.is_crossing(under)).then_some(beyond)
+}
+
+/// Picks one of `options`, keeping to the axis favoured by `bias` as often as it asks.
+fn choose_biased<R: Rng + ?Sized>(
+  options: &[(Direction, Coord)],
+  bias: Bias,
+  rng: &mut R,
+) -> Option<(Direction, Coord)> {
+  let Some((favoured, strength)) = bias.axis() else {
+    return options.choose(rng).copied();
+  };
+  let along: Vec<(Direction, Coord)> = options
+    .iter()
+    .copied()
+    .filter(|(dir, _)| favoured.contains(dir))
+    .collect();
+  if !along.is_empty() && rng.gen::<f32>() < strength.clamp(0.0, 1.0) {
+    along.choose(rng).copied()
+  } else {
+    options.choose(rng).copied()
+  }
+}
+
+/// Randomized Prim: grows the maze by opening a random wall on its frontier.
+fn prim<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  let mut visited = unvisited(maze);
+  let first = rng.gen_range(0..maze.cell_count());
+  // Restarts in unreached regions, as in `recursive_backtracker`.
+  for start in std::iter::once(first).chain(0..maze.cell_count()) {
+    if visited[start] {
+      continue;
+    }
+    visited[start] = true;
+    let start = maze.coord(start);
+    let mut frontier: Vec<(Coord, Direction)> = dirs.iter().map(|&dir| (start, dir)).collect();
+
+    while !frontier.is_empty() {
+      let (cell, dir) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+      let Some(next) = maze.neighbor(cell, dir) else {
+        continue;
+      };
+      if visited[maze.index(next)] {
+        continue;
+      }
+      tracker.carve(maze, cell, dir)?;
+      visited[maze.index(next)] = true;
+      frontier.extend(dirs.iter().map(|&dir| (next, dir)));
+    }
+  }
+  Ok(())
+}
+
+/// Hunt-and-kill: walks to random unvisited neighbours until stuck, then hunts for the first
+/// unvisited cell, in index order, that borders the carved part and continues from there.
+fn hunt_and_kill<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  let mut visited = unvisited(maze);
+  let first = rng.gen_range(0..maze.cell_count());
+  let mut current = (!visited[first]).then(|| maze.coord(first));
+  visited[first] = true;
+  // Every cell below `lowest` is visited, so hunts can start there.
+  let mut lowest = 0;
+
+  loop {
+    while let Some(cell) = current {
+      let options: Vec<(Direction, Coord)> = dirs
+        .iter()
+        .filter_map(|&dir| maze.neighbor(cell, dir).map(|next| (dir, next)))
+        .filter(|&(_, next)| !visited[maze.index(next)])
+        .collect();
+      current = match options.choose(rng) {
+        Some(&(dir, next)) => {
+          tracker.carve(maze, cell, dir)?;
+          visited[maze.index(next)] = true;
+          Some(next)
+        }
+        None => None,
+      };
+    }
+
+    while lowest < maze.cell_count() && visited[lowest] {
+      lowest += 1;
+    }
+    if lowest == maze.cell_count() {
+      return Ok(());
+    }
+    let hunted = (lowest..maze.cell_count())
+      .filter(|&index| !visited[index])
+      .find_map(|index| {
+        let cell = maze.coord(index);
+        let joins: Vec<Direction> = dirs
+          .iter()
+          .copied()
+          .filter(|&dir| {
+            maze
+              .neighbor(cell, dir)
+              .is_some_and(|next| visited[maze.index(next)])
+          })
+          .collect();
+        (!joins.is_empty()).then_some((cell, joins))
+      });
+    let cell = match hunted {
+      Some((cell, joins)) => {
+        let &dir = joins.choose(rng).expect("hunted cells border the maze");
+        tracker.carve(maze, cell, dir)?;
+        cell
+      }
+      // Nothing borders the carved part, so the rest is another region: start over there.
+      None => maze.coord(lowest),
+    };
+    visited[maze.index(cell)] = true;
+    current = Some(cell);
+  }
+}
+
+/// Aldous-Broder: a random walk that carves into every cell it enters for the first time,
+/// giving each spanning tree the same chance.
+fn aldous_broder<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  if maze.cell_count() > ALDOUS_BRODER_MAX_CELLS {
+    return Err(GenerateError::TooLarge {
+      algorithm: Algorithm::AldousBroder,
+      cells: maze.cell_count(),
+      limit: ALDOUS_BRODER_MAX_CELLS,
+    });
+  }
+  let mut visited = unvisited(maze);
+  let first = rng.gen_range(0..maze.cell_count());
+  // Restarts in unreached regions, as in `recursive_backtracker`.
+  for start in std::iter::once(first).chain(0..maze.cell_count()) {
+    if visited[start] {
+      continue;
+    }
+    visited[start] = true;
+    let mut current = maze.coord(start);
+    // The walk can only stop once it has seen its whole region.
+    let mut remaining = region_size(maze, current, dirs) - 1;
+
+    while remaining > 0 {
+      let options: Vec<(Direction, Coord)> = dirs
+        .iter()
+        .filter_map(|&dir| maze.neighbor(current, dir).map(|next| (dir, next)))
+        .collect();
+      let &(dir, next) = options
+        .choose(rng)
+        .expect("a region with unvisited cells has no isolated cell");
+      if !visited[maze.index(next)] {
+        tracker.carve(maze, current, dir)?;
+        visited[maze.index(next)] = true;
+        remaining -= 1;
+      }
+      current = next;
+    }
+  }
+  Ok(())
+}
+
+/// Cells reachable from `start` through the faces in `dirs`, walls ignored.
+fn region_size(maze: &Maze, start: Coord, dirs: &[Direction]) -> usize {
+  let mut seen = vec![false; maze.cell_count()];
+  seen[maze.index(start)] = true;
+  let mut stack = vec![start];
+  let mut size = 0;
+  while let Some(cell) = stack.pop() {
+    size += 1;
+    for &dir in dirs {
+      if let Some(next) = maze.neighbor(cell, dir) {
+        if !std::mem::replace(&mut seen[maze.index(next)], true) {
+          stack.push(next);
+        }
+      }
+    }
+  }
+  size
+}
+
+/// Randomized Kruskal: opens walls in random order whenever they join two separate trees.
+fn kruskal<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  // Each wall is listed once, from the cell on its negative side.
+  let forward: Vec<Direction> = [Direction::East, Direction::South, Direction::Up]
+    .into_iter()
+    .filter(|dir| dirs.contains(dir))
+    .collect();
+  let edges: Vec<(Coord, Direction)> = (0..maze.cell_count())
+    .map(|index| maze.coord(index))
+    .flat_map(|cell| forward.iter().map(move |&dir| (cell, dir)))
+    .filter(|&(cell, dir)| maze.neighbor(cell, dir).is_some())
+    .collect();
+  let edges = shuffle_edges(edges, rng.gen());
+
+  // Merging stays sequential: whether a wall opens depends on every wall opened before it.
+  let mut sets = DisjointSet::new(maze.cell_count());
+  for (cell, dir) in edges {
+    let next = maze
+      .neighbor(cell, dir)
+      .expect("edges only point inside the grid");
+    if sets.union(maze.index(cell), maze.index(next)) {
+      tracker.carve(maze, cell, dir)?;
+    }
+  }
+  Ok(())
+}
+
+/// Work left for [`recursive_division`]; boxes run from `min` to `max` exclusive.
+enum Division {
+  /// Split the box, unless it is a single cell.
+  Split { min: Coord, max: Coord },
+  /// Open gaps in the wall between layers `at - 1` and `at` of `axis`, once both halves are
+  /// done.
+  Join {
+    min: Coord,
+    max: Coord,
+    axis: usize,
+    at: u32,
+  },
+}
+
+/// Recursive division, run bottom-up since every wall starts out standing.
+///
+/// Each box is cut along one of its axes, picked in proportion to its length, so floors
+/// between levels come up now and then. Once both halves are finished, the cut opens one gap
+/// for every pair of parts it would otherwise keep apart: exactly one in a full block, more
+/// where the mask splits a half.
+fn recursive_division<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  // The positive direction along each axis.
+  let forward = [Direction::East, Direction::South, Direction::Up];
+  let (width, height, depth) = maze.dimensions();
+  let mut pending: Vec<Division> = if dirs.contains(&Direction::Up) {
+    vec![Division::Split {
+      min: [0, 0, 0],
+      max: [width, height, depth],
+    }]
+  } else {
+    (0..depth)
+      .map(|z| Division::Split {
+        min: [0, 0, z],
+        max: [width, height, z + 1],
+      })
+      .collect()
+  };
+  let mut sets = DisjointSet::new(maze.cell_count());
+
+  while let Some(step) = pending.pop() {
+    match step {
+      Division::Split { min, max } => {
+        let cuts: Vec<(usize, u32)> = (0..3)
+          .filter(|&axis| dirs.contains(&forward[axis]))
+          .map(|axis| (axis, max[axis] - min[axis] - 1))
+          .filter(|&(_, cuts)| cuts > 0)
+          .collect();
+        let total: u32 = cuts.iter().map(|&(_, cuts)| cuts).sum();
+        if total == 0 {
+          continue;
+        }
+        let mut pick = rng.gen_range(0..total);
+        let mut axis = cuts[0].0;
+        for &(candidate, cuts) in &cuts {
+          if pick < cuts {
+            axis = candidate;
+            break;
+          }
+          pick -= cuts;
+        }
+        let at = min[axis] + 1 + pick;
+        let (mut lower_max, mut upper_min) = (max, min);
+        lower_max[axis] = at;
+        upper_min[axis] = at;
+        pending.push(Division::Join { min, max, axis, at });
+        pending.push(Division::Split {
+          min,
+          max: lower_max,
+        });
+        pending.push(Division::Split {
+          min: upper_min,
+          max,
+        });
+      }
+      Division::Join { min, max, axis, at } => {
+        let dir = forward[axis];
+        // The layer just before the cut, which owns the faces across it.
+        let (mut lo, mut hi) = (min, max);
+        lo[axis] = at - 1;
+        hi[axis] = at;
+        let mut gaps: Vec<Coord> = (lo[2]..hi[2])
+          .flat_map(|z| (lo[1]..hi[1]).flat_map(move |y| (lo[0]..hi[0]).map(move |x| [x, y, z])))
+          .filter(|&cell| maze.neighbor(cell, dir).is_some())
+          .collect();
+        gaps.shuffle(rng);
+        for cell in gaps {
+          let next = maze.neighbor(cell, dir).expect("gaps join active cells");
+          if sets.union(maze.index(cell), maze.index(next)) {
+            tracker.carve(maze, cell, dir)?;
+          }
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Orders `edges` by a weight hashed from `key` and each edge's position in the list.
+///
+/// The weights are distinct and need no shared RNG state, so they can be computed and sorted
+/// on any number of threads with the same result.
+fn shuffle_edges(edges: Vec<(Coord, Direction)>, key: u64) -> Vec<(Coord, Direction)> {
+  let weight = |(position, edge): (usize, (Coord, Direction))| (mix(key ^ position as u64), edge);
+  #[cfg(feature = "parallel")]
+  let weighted = {
+    use rayon::prelude::*;
+    let mut weighted: Vec<(u64, (Coord, Direction))> =
+      edges.into_par_iter().enumerate().map(weight).collect();
+    weighted.par_sort_unstable_by_key(|&(weight, _)| weight);
+    weighted
+  };
+  #[cfg(not(feature = "parallel"))]
+  let weighted = {
+    let mut weighted: Vec<(u64, (Coord, Direction))> =
+      edges.into_iter().enumerate().map(weight).collect();
+    weighted.sort_unstable_by_key(|&(weight, _)| weight);
+    weighted
+  };
+  weighted.into_iter().map(|(_, edge)| edge).collect()
+}
+
+/// SplitMix64 finalizer; a bijection, so distinct inputs never share a weight.
+fn mix(mut z: u64) -> u64 {
+  z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  z ^ (z >> 31)
+}
+
+/// Opens `stairs_per_level` vertical passages between every pair of adjacent levels.
+///
+/// Candidates are tried in random order and kept only if they respect `min_spacing` against
+/// every stair already touching either level. A final pass then adds whatever stairs are still
+/// needed to join regions left isolated, ignoring spacing, so every level stays reachable.
+fn place_stairs<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  vertical: VerticalConfig,
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  let (width, height, depth) = maze.dimensions();
+  // Stair footprints touching each level, from below and from above.
+  let mut on_level: Vec<Vec<[u32; 2]>> = vec![Vec::new(); depth as usize];
+  let far_enough = |placed: &[[u32; 2]], [x, y]: [u32; 2]| {
+    placed
+      .iter()
+      .all(|&[px, py]| px.abs_diff(x) + py.abs_diff(y) >= vertical.min_spacing)
+  };
+
+  let mut candidates: Vec<Coord> = Vec::new();
+  for z in 0..depth.saturating_sub(1) {
+    let mut columns: Vec<[u32; 2]> = (0..height)
+      .flat_map(|y| (0..width).map(move |x| [x, y]))
+      .filter(|&[x, y]| maze.neighbor([x, y, z], Direction::Up).is_some())
+      .collect();
+    columns.shuffle(rng);
+    let (lower, upper) = (z as usize, z as usize + 1);
+    let mut chosen: Vec<[u32; 2]> = Vec::new();
+    for &column in &columns {
+      if chosen.len() == vertical.stairs_per_level as usize {
+        break;
+      }
+      let spaced = far_enough(&on_level[lower], column) && far_enough(&on_level[upper], column);
+      if spaced && far_enough(&chosen, column) {
+        chosen.push(column);
+      }
+    }
+    for &[x, y] in &chosen {
+      tracker.carve(maze, [x, y, z], Direction::Up)?;
+    }
+    on_level[lower].extend(&chosen);
+    on_level[upper].extend(&chosen);
+    candidates.extend(columns.into_iter().map(|[x, y]| [x, y, z]));
+  }
+
+  let mut sets = DisjointSet::new(maze.cell_count());
+  for index in 0..maze.cell_count() {
+    let cell = maze.coord(index);
+    for (_, next) in maze.open_neighbors(cell) {
+      sets.union(index, maze.index(next));
+    }
+  }
+  for cell in candidates {
+    let above = maze.index([cell[0], cell[1], cell[2] + 1]);
+    if sets.union(maze.index(cell), above) {
+      tracker.carve(maze, cell, Direction::Up)?;
+    }
+  }
+  Ok(())
+}
+
+/// Union-find with path halving and union by size.
+struct DisjointSet {
+  parent: Vec<usize>,
+  size: Vec<usize>,
+}
+
+impl DisjointSet {
+  fn new(len: usize) -> DisjointSet {
+    DisjointSet {
+      parent: (0..len).collect(),
+      size: vec![1; len],
+    }
+  }
+
+  fn find(&mut self, mut x: usize) -> usize {
+    while self.parent[x] != x {
+      self.parent[x] = self.parent[self.parent[x]];
+      x = self.parent[x];
+    }
+    x
+  }
+
+  /// Merges the sets holding `a` and `b`; returns `false` if they were already joined.
+  fn union(&mut self, a: usize, b: usize) -> bool {
+    let (mut a, mut b) = (self.find(a), self.find(b));
+    if a == b {
+      return false;
+    }
+    if self.size[a] < self.size[b] {
+      std::mem::swap(&mut a, &mut b);
+    }
+    self.parent[b] = a;
+    self.size[a] += self.size[b];
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::maze::ALL_WALLS;
+
+  /// Open faces between two active cells, each counted once.
+  fn passages(maze: &Maze) -> usize {
+    let open: usize = (0..maze.cell_count())
+      .map(|index| maze.open_neighbors(maze.coord(index)).count())
+      .sum();
+    open / 2
+  }
+
+  #[test]
+  fn the_same_seed_carves_the_same_maze() {
+    for algorithm in Algorithm::ALL {
+      let first = Maze::generate_with_seed((9, 8, 3), 42, algorithm).unwrap();
+      let second = Maze::generate_with_seed((9, 8, 3), 42, algorithm).unwrap();
+      assert_eq!(first.to_json(), second.to_json(), "{algorithm:?}");
+    }
+  }
+
+  #[test]
+  fn different_seeds_diverge() {
+    for algorithm in Algorithm::ALL {
+      let first = Maze::generate_with_seed((9, 8, 3), 42, algorithm).unwrap();
+      let second = Maze::generate_with_seed((9, 8, 3), 43, algorithm).unwrap();
+      assert_ne!(first.wall_data(), second.wall_data(), "{algorithm:?}");
+    }
+  }
+
+  #[test]
+  fn stairs_join_the_bottom_entrance_to_the_top_exit() {
+    let config = GenConfig {
+      vertical: Some(VerticalConfig {
+        stairs_per_level: 2,
+        min_spacing: 3,
+      }),
+      ..GenConfig::new((8, 8, 4), Algorithm::RecursiveBacktracker, 11)
+    };
+    let maze = Maze::generate(&config).unwrap();
+    assert_eq!(maze.entrance()[2], 0);
+    assert_eq!(maze.exit()[2], 3);
+    let path = solver::bfs(&maze, maze.entrance(), maze.exit()).unwrap();
+    assert_eq!(path.last(), Some(&maze.exit()));
+  }
+
+  #[test]
+  fn carves_an_l_shaped_mask() {
+    let mut mask = Mask::new(6, 6, 1);
+    for x in 2..6 {
+      for y in 0..4 {
+        mask.set_active([x, y, 0], false);
+      }
+    }
+    for algorithm in Algorithm::ALL {
+      let maze = Maze::generate_masked(&mask, algorithm, 3).unwrap();
+      assert_eq!(passages(&maze), mask.active_count() - 1, "{algorithm:?}");
+      assert!((0..maze.cell_count())
+        .map(|index| maze.coord(index))
+        .all(|cell| mask.is_active(cell) || maze.walls(cell) == ALL_WALLS));
+      assert!(solver::bfs(&maze, [0, 0, 0], [5, 5, 0]).is_ok());
+    }
+  }
+
+  #[test]
+  fn rejects_a_mask_split_into_islands() {
+    let mut mask = Mask::new(5, 3, 1);
+    for y in 0..3 {
+      mask.set_active([2, y, 0], false);
+    }
+    let Err(GenerateError::Disconnected { mut unreachable }) =
+      Maze::generate_masked(&mask, Algorithm::Prim, 3)
+    else {
+      panic!("a split mask must not generate");
+    };
+    assert_eq!(unreachable.len(), 1);
+    unreachable[0].sort();
+    let island: Vec<Coord> = (3..5)
+      .flat_map(|x| (0..3).map(move |y| [x, y, 0]))
+      .collect();
+    assert_eq!(unreachable[0], island);
+  }
+
+  #[test]
+  fn progress_reaches_the_total() {
+    let config = GenConfig::new((20, 20, 2), Algorithm::Kruskal, 4);
+    let mut last = None;
+    let maze =
+      Maze::generate_with_progress(&config, &AtomicBool::new(false), |p| last = Some(p)).unwrap();
+    let last = last.unwrap();
+    assert_eq!(last.carved, last.total);
+    assert_eq!(last.total, 20 * 20 * 2 - 1);
+    assert_eq!(maze, Maze::generate(&config).unwrap());
+  }
+
+  #[test]
+  fn raising_the_cancel_flag_stops_generation() {
+    let config = GenConfig::new((20, 20, 2), Algorithm::RecursiveBacktracker, 4);
+    let cancel = AtomicBool::new(false);
+    let mut reports = 0;
+    let result = Maze::generate_with_progress(&config, &cancel, |_| {
+      reports += 1;
+      cancel.store(true, Ordering::Relaxed);
+    });
+    assert_eq!(result, Err(GenerateError::Cancelled));
+    assert_eq!(reports, 1);
+  }
+
+  #[test]
+  fn shuffled_edges_match_a_sequential_sort() {
+    let maze = Maze::new(30, 20, 3);
+    let edges: Vec<(Coord, Direction)> = (0..maze.cell_count())
+      .map(|index| (maze.coord(index), Direction::ALL[index % 6]))
+      .collect();
+    let mut expected: Vec<(u64, (Coord, Direction))> = edges
+      .iter()
+      .enumerate()
+      .map(|(position, &edge)| (mix(99 ^ position as u64), edge))
+      .collect();
+    expected.sort_by_key(|&(weight, _)| weight);
+    let expected: Vec<(Coord, Direction)> = expected.into_iter().map(|(_, edge)| edge).collect();
+    assert_eq!(shuffle_edges(edges, 99), expected);
+  }
+
+  /// Pinned so the build with the `parallel` feature is held to the same maze.
+  #[test]
+  fn kruskal_carves_the_same_maze_with_or_without_rayon() {
+    let maze = Maze::generate_with_seed((40, 30, 4), 12, Algorithm::Kruskal).unwrap();
+    assert_eq!(format!("{:016x}", maze.content_hash()), "3094c0674a40e666");
+  }
+
+  /// Run with `--ignored`, once with and once without `--features parallel`, to compare.
+  #[test]
+  #[ignore]
+  fn time_kruskal_on_a_large_maze() {
+    let started = std::time::Instant::now();
+    Maze::generate_with_seed((150, 150, 8), 1, Algorithm::Kruskal).unwrap();
+    let parallel = cfg!(feature = "parallel");
+    println!(
+      "kruskal 150x150x8 (parallel: {parallel}): {:?}",
+      started.elapsed()
+    );
+  }
+
+  #[test]
+  fn farthest_pair_spans_the_diameter() {
+    let config = GenConfig {
+      portals: PortalStrategy::FarthestPair,
+      ..GenConfig::new((6, 5, 2), Algorithm::Prim, 21)
+    };
+    let maze = Maze::generate(&config).unwrap();
+    let diameter = (0..maze.cell_count())
+      .flat_map(|index| solver::distance_field(&maze, maze.coord(index)))
+      .max()
+      .unwrap();
+    let path = solver::bfs(&maze, maze.entrance(), maze.exit()).unwrap();
+    assert_eq!(path.len() as u32 - 1, diameter);
+  }
+
+  #[test]
+  fn rejects_an_explicit_portal_outside_the_maze() {
+    let config = GenConfig {
+      portals: PortalStrategy::Explicit([[0, 0, 0], [6, 0, 0]]),
+      ..GenConfig::new((6, 5, 2), Algorithm::Prim, 21)
+    };
+    assert_eq!(
+      Maze::generate(&config),
+      Err(GenerateError::BadPortal([6, 0, 0]))
+    );
+  }
+
+  /// Mean length of the maximal runs of open east faces.
+  fn mean_east_run(maze: &Maze) -> f64 {
+    let (mut runs, mut total) = (0u32, 0u32);
+    for index in 0..maze.cell_count() {
+      let mut cell = maze.coord(index);
+      // Runs start where the west face is closed.
+      if maze.has_wall(cell, Direction::East) || !maze.has_wall(cell, Direction::West) {
+        continue;
+      }
+      runs += 1;
+      while !maze.has_wall(cell, Direction::East) {
+        total += 1;
+        cell = maze.neighbor(cell, Direction::East).unwrap();
+      }
+    }
+    f64::from(total) / f64::from(runs)
+  }
+
+  #[test]
+  fn a_horizontal_bias_lengthens_east_west_runs() {
+    let sample = |bias| -> (f64, f64) {
+      let runs: Vec<f64> = (0..40)
+        .map(|seed| {
+          let config = GenConfig {
+            bias,
+            ..GenConfig::new((30, 30, 1), Algorithm::RecursiveBacktracker, seed)
+          };
+          mean_east_run(&Maze::generate(&config).unwrap())
+        })
+        .collect();
+      let n = runs.len() as f64;
+      let mean = runs.iter().sum::<f64>() / n;
+      let variance = runs.iter().map(|run| (run - mean).powi(2)).sum::<f64>() / (n - 1.0);
+      (mean, variance / n)
+    };
+    let (plain, plain_error) = sample(Bias::None);
+    let (biased, biased_error) = sample(Bias::Horizontal(0.9));
+    // More than five standard errors apart.
+    let z = (biased - plain) / (plain_error + biased_error).sqrt();
+    assert!(z > 5.0, "runs {plain:.2} vs {biased:.2}, z = {z:.1}");
+  }
+
+  #[test]
+  fn hunt_and_kill_and_aldous_broder_carve_spanning_trees() {
+    for algorithm in [Algorithm::HuntAndKill, Algorithm::AldousBroder] {
+      let maze = Maze::generate_with_seed((10, 10, 3), 17, algorithm).unwrap();
+      assert_eq!(passages(&maze), 10 * 10 * 3 - 1, "{algorithm:?}");
+      let distances = solver::distance_field(&maze, [0, 0, 0]);
+      assert!(distances.iter().all(|&distance| distance != u32::MAX));
+    }
+  }
+
+  #[test]
+  fn limits_reject_an_overflowing_cell_count() {
+    let dims = (u32::MAX, u32::MAX, u32::MAX);
+    let limits = GenerationLimits {
+      max_cells: u64::MAX,
+    };
+    assert_eq!(
+      limits.check(dims),
+      Err(GenerateError::Oversized {
+        dims,
+        limit: u64::MAX
+      })
+    );
+  }
+
+  #[test]
+  fn limits_reject_a_request_just_over_the_cap() {
+    let limits = GenerationLimits { max_cells: 1000 };
+    assert_eq!(limits.check((10, 10, 10)), Ok(1000));
+    let config = GenConfig {
+      limits,
+      ..GenConfig::new((10, 10, 11), Algorithm::Kruskal, 1)
+    };
+    assert_eq!(
+      Maze::generate(&config),
+      Err(GenerateError::Oversized {
+        dims: (10, 10, 11),
+        limit: 1000
+      })
+    );
+  }
+
+  #[test]
+  fn recursive_division_leaves_exactly_one_path() {
+    let maze = Maze::generate_with_seed((9, 8, 2), 6, Algorithm::RecursiveDivision).unwrap();
+    assert_eq!(passages(&maze), 9 * 8 * 2 - 1);
+    let (start, end) = ([0, 0, 0], [8, 7, 1]);
+    let path = solver::bfs(&maze, start, end).unwrap();
+    // Closing any passage on the path cuts the end off, so there is no other way round.
+    for pair in path.windows(2) {
+      let (dir, _) = maze
+        .open_neighbors(pair[0])
+        .find(|&(_, next)| next == pair[1])
+        .unwrap();
+      let mut cut = maze.clone();
+      cut.close(pair[0], dir);
+      assert!(solver::bfs(&cut, start, end).is_err(), "{pair:?}");
+    }
+  }
+}
@@ -0,0 +1,191 @@
+//! Loop-adding post-processing for perfect mazes.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::maze::{Coord, Direction, Maze, Topology};
+use crate::solver;
+
+/// Braiding that changes with the distance from the entrance, for levels that start open and
+/// grow harder towards the exit.
+///
+/// Each dead end is opened with a chance running linearly from `near` at the entrance to `far`
+/// at the cell farthest from it, both clamped to `0.0..=1.0`. Cells the entrance cannot reach
+/// count as farthest.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyGradient {
+  pub near: f32,
+  pub far: f32,
+}
+
+/// Opens a wall in `removal_ratio` (clamped to `0.0..=1.0`) of the maze's dead ends, turning
+/// them into loops.
+///
+/// Each chosen dead end knocks through to a walled-off neighbour, preferring one that is a dead
+/// end too so a single wall removes both. Opening walls never disconnects anything, and a
+/// higher ratio opens a superset of the walls a lower one would with the same RNG state.
+pub fn braid(maze: &mut Maze, removal_ratio: f32, rng: &mut impl Rng) {
+  braid_through(maze, removal_ratio, &Direction::ALL, rng);
+}
+
+/// [`braid`] limited to the faces in `dirs`, so planar mazes do not grow extra stairs.
+pub(super) fn braid_through<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  removal_ratio: f32,
+  dirs: &[Direction],
+  rng: &mut R,
+) {
+  let mut dead_ends: Vec<Coord> = (0..maze.cell_count())
+    .map(|index| maze.coord(index))
+    .filter(|&cell| is_dead_end(maze, cell))
+    .collect();
+  dead_ends.shuffle(rng);
+  let count = (dead_ends.len() as f32 * removal_ratio.clamp(0.0, 1.0)).round() as usize;
+
+  for &cell in dead_ends.iter().take(count) {
+    knock_through(maze, cell, dirs, rng);
+  }
+}
+
+/// Opens dead ends as `gradient` asks, measuring distances under `topology`.
+pub(super) fn braid_graded<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  gradient: DifficultyGradient,
+  dirs: &[Direction],
+  topology: &dyn Topology,
+  rng: &mut R,
+) {
+  let distances = solver::distance_field_in(maze, maze.entrance(), topology);
+  let farthest = distances
+    .iter()
+    .copied()
+    .filter(|&distance| distance != u32::MAX)
+    .max()
+    .unwrap_or(0)
+    .max(1);
+  let (near, far) = (gradient.near.clamp(0.0, 1.0), gradient.far.clamp(0.0, 1.0));
+  let mut dead_ends: Vec<Coord> = (0..maze.cell_count())
+    .map(|index| maze.coord(index))
+    .filter(|&cell| is_dead_end(maze, cell))
+    .collect();
+  dead_ends.shuffle(rng);
+  for cell in dead_ends {
+    let t = distances[maze.index(cell)].min(farthest) as f32 / farthest as f32;
+    if rng.gen::<f32>() < near + (far - near) * t {
+      knock_through(maze, cell, dirs, rng);
+    }
+  }
+}
+
+/// Opens one wall of the dead end `cell`, preferring a neighbour that is a dead end too.
+fn knock_through<R: Rng + ?Sized>(maze: &mut Maze, cell: Coord, dirs: &[Direction], rng: &mut R) {
+  // An earlier knock-through may already have opened this one up.
+  if !is_dead_end(maze, cell) {
+    return;
+  }
+  let walled: Vec<(Direction, Coord)> = dirs
+    .iter()
+    .filter(|&&dir| maze.has_wall(cell, dir))
+    .filter_map(|&dir| maze.neighbor(cell, dir).map(|next| (dir, next)))
+    .collect();
+  let paired: Vec<(Direction, Coord)> = walled
+    .iter()
+    .copied()
+    .filter(|&(_, next)| is_dead_end(maze, next))
+    .collect();
+  let options = if paired.is_empty() { &walled } else { &paired };
+  if let Some(&(dir, _)) = options.choose(rng) {
+    maze.carve(cell, dir);
+  }
+}
+
+fn is_dead_end(maze: &Maze, cell: Coord) -> bool {
+  maze.open_neighbors(cell).count() == 1
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::SeedableRng;
+  use rand_chacha::ChaCha8Rng;
+
+  use super::*;
+  use crate::generator::Algorithm;
+
+  fn dead_ends(maze: &Maze) -> usize {
+    (0..maze.cell_count())
+      .filter(|&index| is_dead_end(maze, maze.coord(index)))
+      .count()
+  }
+
+  fn braided(ratio: f32) -> Maze {
+    let mut maze =
+      Maze::generate_with_seed((12, 12, 2), 8, Algorithm::RecursiveBacktracker).unwrap();
+    braid(&mut maze, ratio, &mut ChaCha8Rng::seed_from_u64(1));
+    maze
+  }
+
+  #[test]
+  fn a_zero_ratio_changes_nothing() {
+    let perfect =
+      Maze::generate_with_seed((12, 12, 2), 8, Algorithm::RecursiveBacktracker).unwrap();
+    assert_eq!(braided(0.0), perfect);
+  }
+
+  #[test]
+  fn dead_ends_fall_as_the_ratio_rises() {
+    let counts: Vec<usize> = [0.0, 0.25, 0.5, 0.75, 1.0]
+      .into_iter()
+      .map(|ratio| dead_ends(&braided(ratio)))
+      .collect();
+    assert!(
+      counts.windows(2).all(|pair| pair[1] < pair[0]),
+      "{counts:?}"
+    );
+    assert_eq!(counts[4], 0);
+  }
+
+  #[test]
+  fn braiding_keeps_every_cell_reachable() {
+    for ratio in [0.3, 1.0] {
+      let maze = braided(ratio);
+      let distances = solver::distance_field(&maze, [0, 0, 0]);
+      assert!(distances.iter().all(|&distance| distance != u32::MAX));
+    }
+  }
+
+  #[test]
+  fn a_steep_gradient_leaves_dead_ends_far_away() {
+    let perfect = Maze::generate_with_seed((30, 30, 1), 2, Algorithm::Prim).unwrap();
+    let distances = solver::distance_field(&perfect, perfect.entrance());
+    let farthest = distances.iter().copied().max().unwrap();
+    let mut maze = perfect.clone();
+    let gradient = DifficultyGradient {
+      near: 1.0,
+      far: 0.0,
+    };
+    let rng = &mut ChaCha8Rng::seed_from_u64(4);
+    braid_graded(
+      &mut maze,
+      gradient,
+      &Direction::ALL,
+      &crate::maze::Orthogonal6,
+      rng,
+    );
+
+    // Dead ends per cell among the cells in each third of the distance range.
+    let density = |third: u32| {
+      let cells: Vec<usize> = (0..maze.cell_count())
+        .filter(|&index| (distances[index] * 3 / (farthest + 1)) == third)
+        .collect();
+      let dead = cells
+        .iter()
+        .filter(|&&index| is_dead_end(&maze, maze.coord(index)))
+        .count();
+      dead as f64 / cells.len() as f64
+    };
+    let (near, far) = (density(0), density(2));
+    assert!(far > near + 0.05, "near {near:.3}, far {far:.3}");
+  }
+}
@@ -0,0 +1,284 @@
+//! Fluent construction of generated mazes.
+
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+
+use super::{
+  Algorithm, Bias, DifficultyGradient, GenConfig, GenerateError, GenerationLimits, LockConfig,
+  PortalStrategy, Progress, RngKind, Symmetry, VerticalConfig,
+};
+use crate::maze::{Mask, Maze, Orthogonal6, Topology, WrapMode};
+
+/// Collects generation options one setter at a time, then carves the maze.
+///
+/// Only the dimensions are required. Everything else defaults to what [`GenConfig::new`]
+/// picks, on the [`Orthogonal6`] topology with [`Algorithm::RecursiveBacktracker`], and the
+/// seed to a fresh random one drawn when the builder is created, so building twice carves the
+/// same maze.
+#[derive(Clone)]
+pub struct MazeBuilder {
+  dims: Option<(u32, u32, u32)>,
+  algorithm: Algorithm,
+  seed: u64,
+  rng: RngKind,
+  vertical: Option<VerticalConfig>,
+  mask: Option<Mask>,
+  braiding: f32,
+  gradient: Option<DifficultyGradient>,
+  portals: PortalStrategy,
+  bias: Bias,
+  weave: bool,
+  symmetry: Option<Symmetry>,
+  locks: Option<LockConfig>,
+  wrap: WrapMode,
+  limits: GenerationLimits,
+  topology: &'static dyn Topology,
+}
+
+impl Default for MazeBuilder {
+  fn default() -> Self {
+    MazeBuilder {
+      dims: None,
+      algorithm: Algorithm::RecursiveBacktracker,
+      seed: fresh_seed(),
+      rng: RngKind::CURRENT,
+      vertical: None,
+      mask: None,
+      braiding: 0.0,
+      gradient: None,
+      portals: PortalStrategy::OppositeCorners,
+      bias: Bias::None,
+      weave: false,
+      symmetry: None,
+      locks: None,
+      wrap: WrapMode::None,
+      limits: GenerationLimits::default(),
+      topology: &Orthogonal6,
+    }
+  }
+}
+
+impl MazeBuilder {
+  pub fn new() -> MazeBuilder {
+    MazeBuilder::default()
+  }
+
+  pub fn dimensions(mut self, width: u32, height: u32, depth: u32) -> MazeBuilder {
+    self.dims = Some((width, height, depth));
+    self
+  }
+
+  pub fn algorithm(mut self, algorithm: Algorithm) -> MazeBuilder {
+    self.algorithm = algorithm;
+    self
+  }
+
+  pub fn seed(mut self, seed: u64) -> MazeBuilder {
+    self.seed = seed;
+    self
+  }
+
+  pub fn rng(mut self, rng: RngKind) -> MazeBuilder {
+    self.rng = rng;
+    self
+  }
+
+  pub fn vertical(mut self, vertical: VerticalConfig) -> MazeBuilder {
+    self.vertical = Some(vertical);
+    self
+  }
+
+  pub fn mask(mut self, mask: Mask) -> MazeBuilder {
+    self.mask = Some(mask);
+    self
+  }
+
+  pub fn braiding(mut self, braiding: f32) -> MazeBuilder {
+    self.braiding = braiding;
+    self
+  }
+
+  pub fn gradient(mut self, gradient: DifficultyGradient) -> MazeBuilder {
+    self.gradient = Some(gradient);
+    self
+  }
+
+  pub fn portals(mut self, portals: PortalStrategy) -> MazeBuilder {
+    self.portals = portals;
+    self
+  }
+
+  pub fn bias(mut self, bias: Bias) -> MazeBuilder {
+    self.bias = bias;
+    self
+  }
+
+  pub fn weave(mut self, weave: bool) -> MazeBuilder {
+    self.weave = weave;
+    self
+  }
+
+  pub fn symmetry(mut self, symmetry: Symmetry) -> MazeBuilder {
+    self.symmetry = Some(symmetry);
+    self
+  }
+
+  pub fn locks(mut self, locks: LockConfig) -> MazeBuilder {
+    self.locks = Some(locks);
+    self
+  }
+
+  pub fn wrap(mut self, wrap: WrapMode) -> MazeBuilder {
+    self.wrap = wrap;
+    self
+  }
+
+  pub fn limits(mut self, limits: GenerationLimits) -> MazeBuilder {
+    self.limits = limits;
+    self
+  }
+
+  pub fn topology(mut self, topology: &'static dyn Topology) -> MazeBuilder {
+    self.topology = topology;
+    self
+  }
+
+  /// The seed the maze will be carved from, set or drawn.
+  pub fn current_seed(&self) -> u64 {
+    self.seed
+  }
+
+  /// The [`GenConfig`] these options describe, for [`Maze::generate_in`] under
+  /// [`MazeBuilder::current_topology`].
+  pub fn config(&self) -> Result<GenConfig, BuildError> {
+    let (width, height, depth) = self.dims.ok_or(BuildError::MissingDimensions)?;
+    if width == 0 || height == 0 || depth == 0 {
+      return Err(BuildError::Generate(GenerateError::ZeroDimension {
+        width,
+        height,
+        depth,
+      }));
+    }
+    Ok(GenConfig {
+      dims: (width, height, depth),
+      algorithm: self.algorithm,
+      seed: self.seed,
+      rng: self.rng.clone(),
+      vertical: self.vertical,
+      mask: self.mask.clone(),
+      braiding: self.braiding,
+      gradient: self.gradient,
+      portals: self.portals,
+      bias: self.bias,
+      weave: self.weave,
+      symmetry: self.symmetry,
+      locks: self.locks,
+      wrap: self.wrap,
+      limits: self.limits,
+    })
+  }
+
+  pub fn current_topology(&self) -> &'static dyn Topology {
+    self.topology
+  }
+
+  /// Carves the maze.
+  pub fn build(&self) -> Result<Maze, BuildError> {
+    Ok(Maze::generate_in(&self.config()?, self.topology)?)
+  }
+
+  /// [`MazeBuilder::build`] through [`Maze::generate_with_progress_in`].
+  pub fn build_with_progress(
+    &self,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(Progress),
+  ) -> Result<Maze, BuildError> {
+    let config = self.config()?;
+    Ok(Maze::generate_with_progress_in(
+      &config,
+      self.topology,
+      cancel,
+      on_progress,
+    )?)
+  }
+}
+
+/// Random seed that survives the trip through a JS number (53-bit safe integer).
+pub(crate) fn fresh_seed() -> u64 {
+  rand::random::<u64>() >> 11
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+  /// [`MazeBuilder::dimensions`] was never called.
+  MissingDimensions,
+  /// The options were complete but generating from them failed.
+  Generate(GenerateError),
+}
+
+impl fmt::Display for BuildError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BuildError::MissingDimensions => write!(f, "maze dimensions were not set"),
+      BuildError::Generate(e) => e.fmt(f),
+    }
+  }
+}
+
+impl std::error::Error for BuildError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      BuildError::MissingDimensions => None,
+      BuildError::Generate(e) => Some(e),
+    }
+  }
+}
+
+impl From<GenerateError> for BuildError {
+  fn from(e: GenerateError) -> Self {
+    BuildError::Generate(e)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn defaults_match_gen_config() {
+    let builder = MazeBuilder::new().dimensions(5, 4, 2).seed(9);
+    assert_eq!(
+      builder.config().unwrap(),
+      GenConfig::new((5, 4, 2), Algorithm::RecursiveBacktracker, 9)
+    );
+    assert_eq!(builder.current_topology().id(), Orthogonal6.id());
+    assert_eq!(
+      builder.build().unwrap(),
+      Maze::generate_with_seed((5, 4, 2), 9, Algorithm::RecursiveBacktracker).unwrap()
+    );
+  }
+
+  #[test]
+  fn an_unseeded_builder_builds_the_same_maze_twice() {
+    let builder = MazeBuilder::new().dimensions(6, 6, 1);
+    let maze = builder.build().unwrap();
+    assert_eq!(maze.seed(), Some(builder.current_seed()));
+    assert_eq!(builder.build().unwrap(), maze);
+  }
+
+  #[test]
+  fn rejects_missing_or_zero_dimensions() {
+    assert_eq!(
+      MazeBuilder::new().seed(1).build(),
+      Err(BuildError::MissingDimensions)
+    );
+    assert_eq!(
+      MazeBuilder::new().dimensions(3, 0, 2).build(),
+      Err(BuildError::Generate(GenerateError::ZeroDimension {
+        width: 3,
+        height: 0,
+        depth: 2
+      }))
+    );
+  }
+}
@@ -0,0 +1,105 @@
+//! Keys and locked doors along the solution path.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::GenerateError;
+use crate::maze::{Direction, Lock, Maze, Topology};
+use crate::solver;
+
+/// Escape-room mode: the way to the exit is barred by `keys` locked doors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockConfig {
+  /// Doors to place, each with its own key; at most [`solver::MAX_LOCKS`].
+  pub keys: u32,
+}
+
+/// Locks `config.keys` passages of the entrance-to-exit path and hides a key for each.
+///
+/// The doors split the path into stretches of about equal length, away from weave crossings.
+/// Key `i` goes to a cell reachable through doors `0..i` alone, preferring cells the earlier
+/// doors had cut off and, among those, side branches off the path, so collecting the keys in
+/// order always reaches the exit. In a perfect maze every door is unavoidable; loops from
+/// braiding may lead around some of them.
+pub(super) fn place_locks<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  config: LockConfig,
+  topology: &dyn Topology,
+  rng: &mut R,
+) -> Result<(), GenerateError> {
+  if config.keys == 0 {
+    return Ok(());
+  }
+  let path = solver::bfs_in(maze, maze.entrance, maze.exit, topology).unwrap_or_default();
+  // Every step of the path that could hold a door, never the first one so that key 0 has
+  // somewhere to go besides the entrance.
+  let candidates: Vec<(usize, Direction)> = path
+    .windows(2)
+    .enumerate()
+    .skip(1)
+    .filter(|(_, step)| !maze.is_crossing(step[0]) && !maze.is_crossing(step[1]))
+    .filter_map(|(at, step)| {
+      let dir = Direction::ALL
+        .into_iter()
+        .find(|&dir| maze.neighbor(step[0], dir) == Some(step[1]))?;
+      Some((at, dir))
+    })
+    .collect();
+  let room = candidates.len().min(solver::MAX_LOCKS) as u32;
+  if config.keys > room {
+    return Err(GenerateError::TooManyKeys {
+      keys: config.keys,
+      room,
+    });
+  }
+
+  let count = config.keys as usize;
+  let doors: Vec<(usize, Direction)> = (1..=count)
+    .map(|i| candidates[i * candidates.len() / (count + 1)])
+    .collect();
+  let on_path: Vec<bool> = {
+    let mut flags = vec![false; maze.cell_count()];
+    for &cell in &path {
+      flags[maze.index(cell)] = true;
+    }
+    flags
+  };
+  // Walk the maze with the doors not yet unlocked walled up.
+  let mut walled = maze.clone();
+  for &(at, dir) in &doors {
+    walled.close(path[at], dir);
+  }
+  let mut taken = vec![false; maze.cell_count()];
+  taken[maze.index(maze.entrance)] = true;
+  let mut before = vec![false; maze.cell_count()];
+  for &(at, dir) in &doors {
+    let reached: Vec<bool> = solver::distance_field_in(&walled, maze.entrance, topology)
+      .into_iter()
+      .map(|distance| distance != u32::MAX)
+      .collect();
+    let pool = |keep: &dyn Fn(usize) -> bool| -> Vec<usize> {
+      (0..maze.cell_count())
+        .filter(|&index| reached[index] && !taken[index] && keep(index))
+        .collect()
+    };
+    let key = [
+      pool(&|index| !before[index] && !on_path[index]),
+      pool(&|index| !before[index]),
+      pool(&|_| true),
+    ]
+    .into_iter()
+    .find_map(|cells| cells.choose(rng).copied())
+    .expect("the path before the first door holds a cell besides the entrance");
+    taken[key] = true;
+    maze.locks.push(Lock {
+      key: maze.coord(key),
+      door: path[at],
+      dir,
+    });
+    walled.carve(path[at], dir);
+    before = reached;
+  }
+  Ok(())
+}
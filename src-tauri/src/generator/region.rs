@@ -0,0 +1,125 @@
+//! Re-carving part of an existing maze.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{shuffle_edges, DisjointSet, GenerateError};
+use crate::maze::{Coord, Direction, Maze};
+
+impl Maze {
+  /// Re-carves the planar walls between cells of the box `min..=max` (inclusive) from `seed`.
+  ///
+  /// Faces on the box boundary and stairs keep their state, so every cell outside the box is
+  /// bit-for-bit unchanged. Each level of the box gets a fresh random spanning forest, which
+  /// keeps every cell reachable that was before, though paths through the surrounding maze may
+  /// now form loops. Weave crossings inside the box become plain cells, and locks on faces it
+  /// re-carves are dropped along with their keys. The maze no longer matches its seed, so
+  /// [`Maze::seed`] is cleared.
+  pub fn regenerate_region(
+    &mut self,
+    min: Coord,
+    max: Coord,
+    seed: u64,
+  ) -> Result<(), GenerateError> {
+    if !self.contains(max) || (0..3).any(|axis| min[axis] > max[axis]) {
+      return Err(GenerateError::BadRegion { min, max });
+    }
+    let outside = |cell: Coord| (0..3).any(|axis| !(min[axis]..=max[axis]).contains(&cell[axis]));
+    let inside = |cell: Coord| !outside(cell);
+    self.crossings.retain(|&cell| outside(cell));
+    // A planar door between two cells of the box may no longer be a passage at all.
+    let locks = std::mem::take(&mut self.locks);
+    self.locks = locks
+      .into_iter()
+      .filter(|lock| {
+        matches!(lock.dir, Direction::Up | Direction::Down)
+          || outside(lock.door)
+          || self.neighbor(lock.door, lock.dir).is_none_or(outside)
+      })
+      .collect();
+
+    let mut edges: Vec<(Coord, Direction)> = Vec::new();
+    for z in min[2]..=max[2] {
+      for y in min[1]..=max[1] {
+        for x in min[0]..=max[0] {
+          for dir in [Direction::East, Direction::South] {
+            // On a wrapped grid the far side may lie back beyond `min`.
+            if self.neighbor([x, y, z], dir).is_some_and(inside) {
+              self.close([x, y, z], dir);
+              edges.push(([x, y, z], dir));
+            }
+          }
+        }
+      }
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut sets = DisjointSet::new(self.cell_count());
+    for (cell, dir) in shuffle_edges(edges, rng.gen()) {
+      let next = self
+        .neighbor(cell, dir)
+        .expect("edges only join cells inside the box");
+      if sets.union(self.index(cell), self.index(next)) {
+        self.carve(cell, dir);
+      }
+    }
+    self.seed = None;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::{Algorithm, GenConfig};
+  use crate::maze::WrapMode;
+  use crate::solver;
+
+  /// Walls of every cell outside `min..=max`, in index order.
+  fn outside_walls(maze: &Maze, min: Coord, max: Coord) -> Vec<u8> {
+    (0..maze.cell_count())
+      .map(|index| maze.coord(index))
+      .filter(|cell| (0..3).any(|axis| !(min[axis]..=max[axis]).contains(&cell[axis])))
+      .map(|cell| maze.walls(cell))
+      .collect()
+  }
+
+  #[test]
+  fn leaves_the_outside_untouched() {
+    let (min, max) = ([2, 1, 0], [5, 4, 1]);
+    for seed in 0..10 {
+      let mut maze = Maze::generate_with_seed((8, 7, 3), seed, Algorithm::Kruskal).unwrap();
+      let before = outside_walls(&maze, min, max);
+      let original = maze.clone();
+      maze.regenerate_region(min, max, seed + 100).unwrap();
+      assert_eq!(outside_walls(&maze, min, max), before);
+      assert_ne!(maze.wall_data(), original.wall_data(), "seed {seed}");
+    }
+  }
+
+  #[test]
+  fn the_maze_stays_solvable() {
+    let mut maze = Maze::generate_with_seed((8, 7, 3), 3, Algorithm::Prim).unwrap();
+    maze.regenerate_region([0, 0, 0], [4, 6, 2], 9).unwrap();
+    assert_eq!(maze.seed(), None);
+    let distances = solver::distance_field(&maze, maze.entrance());
+    assert!(distances.iter().all(|&distance| distance != u32::MAX));
+    assert!(solver::bfs(&maze, maze.entrance(), maze.exit()).is_ok());
+  }
+
+  #[test]
+  fn a_box_against_a_wrapped_edge_leaves_the_rest_alone() {
+    let (min, max) = ([4, 0, 0], [7, 3, 0]);
+    for seed in 0..20 {
+      let config = GenConfig {
+        wrap: WrapMode::WrapX,
+        ..GenConfig::new((8, 4, 1), Algorithm::Kruskal, seed)
+      };
+      let mut maze = Maze::generate(&config).unwrap();
+      let before = outside_walls(&maze, min, max);
+      maze.regenerate_region(min, max, seed + 100).unwrap();
+      assert_eq!(outside_walls(&maze, min, max), before, "seed {seed}");
+      assert!(solver::bfs(&maze, maze.entrance(), maze.exit()).is_ok());
+    }
+  }
+}
@@ -0,0 +1,240 @@
+//! Mirror-symmetric carving.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{carve, DisjointSet, GenConfig, GenerateError, Tracker};
+use crate::maze::{Coord, Direction, Mask, Maze};
+
+/// Symmetry a generated maze keeps: the walls of every cell match those of its image, with
+/// the faces swapped the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symmetry {
+  /// `x` maps to `width - 1 - x`.
+  MirrorX,
+  /// `y` maps to `height - 1 - y`.
+  MirrorY,
+  /// `z` maps to `depth - 1 - z`, swapping the levels top to bottom.
+  MirrorZ,
+  /// Half a turn around the vertical axis: both `x` and `y` are mirrored, levels stay put.
+  Rotational180,
+}
+
+impl Symmetry {
+  pub const fn id(self) -> &'static str {
+    match self {
+      Symmetry::MirrorX => "mirror_x",
+      Symmetry::MirrorY => "mirror_y",
+      Symmetry::MirrorZ => "mirror_z",
+      Symmetry::Rotational180 => "rotational180",
+    }
+  }
+
+  /// The cell `cell` maps to in a `dims` grid.
+  pub fn image(self, [x, y, z]: Coord, (width, height, depth): (u32, u32, u32)) -> Coord {
+    match self {
+      Symmetry::MirrorX => [width - 1 - x, y, z],
+      Symmetry::MirrorY => [x, height - 1 - y, z],
+      Symmetry::MirrorZ => [x, y, depth - 1 - z],
+      Symmetry::Rotational180 => [width - 1 - x, height - 1 - y, z],
+    }
+  }
+
+  /// The face `dir` maps to.
+  pub fn direction(self, dir: Direction) -> Direction {
+    let flipped = match dir {
+      Direction::East | Direction::West => {
+        matches!(self, Symmetry::MirrorX | Symmetry::Rotational180)
+      }
+      Direction::South | Direction::North => {
+        matches!(self, Symmetry::MirrorY | Symmetry::Rotational180)
+      }
+      Direction::Up | Direction::Down => self == Symmetry::MirrorZ,
+    };
+    if flipped {
+      dir.opposite()
+    } else {
+      dir
+    }
+  }
+}
+
+/// Carves a connected maze that `symmetry` maps onto itself.
+///
+/// The cells that come before their image in index order are carved by the configured
+/// algorithm and mirrored onto the other half. The parts are then joined across the seam with
+/// mirrored pairs of passages, or single passages where a face is its own image. The result
+/// is perfect wherever the symmetry allows a perfect maze at all; otherwise, as with half
+/// turns of an even grid, one pair of passages forms a loop.
+pub(super) fn carve_symmetric<R: Rng + ?Sized>(
+  maze: &mut Maze,
+  config: &GenConfig,
+  symmetry: Symmetry,
+  dirs: &[Direction],
+  rng: &mut R,
+  tracker: &mut Tracker,
+) -> Result<(), GenerateError> {
+  let dims = maze.dimensions();
+  let image = |cell| symmetry.image(cell, dims);
+  if (0..maze.cell_count())
+    .map(|index| maze.coord(index))
+    .any(|cell| maze.is_active(cell) != maze.is_active(image(cell)))
+  {
+    return Err(GenerateError::AsymmetricMask(symmetry));
+  }
+
+  let mut half = maze.clone();
+  let flags = (0..maze.cell_count())
+    .map(|index| {
+      let cell = maze.coord(index);
+      maze.is_active(cell) && index < maze.index(image(cell))
+    })
+    .collect();
+  half.mask = Mask::from_flags(dims.0, dims.1, dims.2, flags);
+  carve(&mut half, config, dirs, rng, tracker)?;
+
+  // Each shared face is looked at once, from the cell west, north or below it.
+  let forward: Vec<Direction> = [Direction::East, Direction::South, Direction::Up]
+    .into_iter()
+    .filter(|dir| dirs.contains(dir))
+    .collect();
+  let mut sets = DisjointSet::new(maze.cell_count());
+  for index in 0..maze.cell_count() {
+    let cell = maze.coord(index);
+    if !half.is_active(cell) {
+      continue;
+    }
+    for &dir in &forward {
+      if half.has_wall(cell, dir) {
+        continue;
+      }
+      let Some(next) = half.neighbor(cell, dir) else {
+        continue;
+      };
+      maze.carve(cell, dir);
+      tracker.carve(maze, image(cell), symmetry.direction(dir))?;
+      sets.union(index, maze.index(next));
+      sets.union(maze.index(image(cell)), maze.index(image(next)));
+    }
+  }
+  for &cell in &half.crossings {
+    maze.crossings.insert(cell);
+    maze.crossings.insert(image(cell));
+  }
+
+  let mut seams: Vec<(Coord, Direction)> = (0..maze.cell_count())
+    .map(|index| maze.coord(index))
+    .flat_map(|cell| forward.iter().map(move |&dir| (cell, dir)))
+    .filter(|&(cell, dir)| {
+      maze
+        .neighbor(cell, dir)
+        .is_some_and(|next| !maze.is_crossing(cell) && !maze.is_crossing(next))
+    })
+    .collect();
+  seams.shuffle(rng);
+  // The strict pass never closes a loop; the second one only runs if that left parts apart.
+  for strict in [true, false] {
+    for &(cell, dir) in &seams {
+      let next = maze.neighbor(cell, dir).expect("seams join active cells");
+      let (a, b) = (maze.index(cell), maze.index(next));
+      let (image_a, image_b) = (maze.index(image(cell)), maze.index(image(next)));
+      let (root_a, root_b) = (sets.find(a), sets.find(b));
+      if root_a == root_b {
+        continue;
+      }
+      let own_image = (image_a, image_b) == (b, a) || (image_a, image_b) == (a, b);
+      if !own_image && strict {
+        let roots = (sets.find(image_a), sets.find(image_b));
+        if roots.0 == roots.1 || roots == (root_a, root_b) || roots == (root_b, root_a) {
+          continue;
+        }
+      }
+      tracker.carve(maze, cell, dir)?;
+      sets.union(a, b);
+      if !own_image {
+        tracker.carve(maze, image(cell), symmetry.direction(dir))?;
+        sets.union(image_a, image_b);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Opens the image of every open face, restoring the symmetry after stairs and braiding.
+pub(super) fn symmetrize(maze: &mut Maze, symmetry: Symmetry) {
+  let dims = maze.dimensions();
+  for index in 0..maze.cell_count() {
+    let cell = maze.coord(index);
+    for dir in Direction::ALL {
+      if maze.is_active(cell) && !maze.has_wall(cell, dir) {
+        maze.carve(symmetry.image(cell, dims), symmetry.direction(dir));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::solver;
+
+  fn symmetric(dims: (u32, u32, u32), symmetry: Symmetry) -> Maze {
+    let config = GenConfig {
+      symmetry: Some(symmetry),
+      ..GenConfig::new(dims, Algorithm::RecursiveBacktracker, 4)
+    };
+    Maze::generate(&config).unwrap()
+  }
+
+  #[test]
+  fn mirror_x_matches_walls_across_the_seam() {
+    let maze = symmetric((7, 5, 2), Symmetry::MirrorX);
+    for index in 0..maze.cell_count() {
+      let [x, y, z] = maze.coord(index);
+      for dir in Direction::ALL {
+        let mirrored = match dir {
+          Direction::East => Direction::West,
+          Direction::West => Direction::East,
+          dir => dir,
+        };
+        assert_eq!(
+          maze.has_wall([x, y, z], dir),
+          maze.has_wall([6 - x, y, z], mirrored),
+          "{:?} of {:?}",
+          dir,
+          [x, y, z]
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn every_symmetry_holds_and_stays_connected() {
+    let all = [
+      Symmetry::MirrorX,
+      Symmetry::MirrorY,
+      Symmetry::MirrorZ,
+      Symmetry::Rotational180,
+    ];
+    for symmetry in all {
+      for dims in [(6, 6, 2), (5, 7, 3)] {
+        let maze = symmetric(dims, symmetry);
+        for index in 0..maze.cell_count() {
+          let cell = maze.coord(index);
+          for dir in Direction::ALL {
+            assert_eq!(
+              maze.has_wall(cell, dir),
+              maze.has_wall(symmetry.image(cell, dims), symmetry.direction(dir)),
+              "{symmetry:?} {dims:?}"
+            );
+          }
+        }
+        let distances = solver::distance_field(&maze, [0, 0, 0]);
+        assert!(distances.iter().all(|&distance| distance != u32::MAX));
+      }
+    }
+  }
+}
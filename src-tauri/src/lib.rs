@@ -0,0 +1,8 @@
+//! Native half of the desktop build: the maze engine behind the webview's Tauri commands.
+
+pub mod commands;
+pub mod export;
+pub mod file;
+pub mod generator;
+pub mod maze;
+pub mod solver;
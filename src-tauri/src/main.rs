@@ -1,6 +1,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-fn main() {
+use std::process::ExitCode;
+
+use procedural_3d_maze::commands::{self, RendererInfo};
+
+fn main() -> ExitCode {
   // Linux WebKitGTK stability / compatibility toggles.
   //
   // Notes:
@@ -15,19 +19,62 @@ fn main() {
     }
 
     // Optional: force a more conservative WebKit compositing mode.
-    if std::env::var_os("P3DM_WEBKIT_NO_COMPOSITING").is_some()
-      && std::env::var_os("WEBKIT_DISABLE_COMPOSITING_MODE").is_none()
+    if RendererInfo::toggle_on("P3DM_WEBKIT_NO_COMPOSITING")
+      && !RendererInfo::toggle_on("WEBKIT_DISABLE_COMPOSITING_MODE")
     {
       std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
     }
 
     // Optional: force software OpenGL (slow, but helps on machines without working GPU drivers).
-    if std::env::var_os("P3DM_SOFTWARE_GL").is_some() && std::env::var_os("LIBGL_ALWAYS_SOFTWARE").is_none() {
+    if RendererInfo::toggle_on("P3DM_SOFTWARE_GL") && !RendererInfo::toggle_on("LIBGL_ALWAYS_SOFTWARE") {
       std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
     }
   }
 
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("error while running the application: {e}");
+      // The usual suspects on Linux; worth including in a bug report.
+      if cfg!(target_os = "linux") {
+        eprintln!("{}", RendererInfo::from_env());
+      }
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run() -> Result<(), tauri::Error> {
   tauri::Builder::default()
+    .manage(commands::GenerationState::default())
+    .invoke_handler(tauri::generate_handler![
+      commands::generate_maze,
+      commands::list_algorithms,
+      commands::generate_batch,
+      commands::generate_preview,
+      commands::generate_maze_progress,
+      commands::cancel_generation,
+      commands::solve_maze,
+      commands::solve_with_keys,
+      commands::explore_maze,
+      commands::solve_steps,
+      commands::distance_field,
+      commands::regenerate_region,
+      commands::cull_dead_ends,
+      commands::join_mazes,
+      commands::maze_hash,
+      commands::maze_graph,
+      commands::export_obj,
+      commands::export_gltf,
+      commands::export_png,
+      commands::export_path_csv,
+      commands::maze_stats,
+      commands::level_connectivity,
+      commands::fairness_report,
+      commands::validate_maze,
+      commands::load_and_solve,
+      commands::regenerate_maze,
+      commands::renderer_diagnostics,
+    ])
     .run(tauri::generate_context!())
-    .expect("error while running tauri application");
 }
@@ -0,0 +1,267 @@
+//! Grid model shared by the generators, solvers and exporters.
+//!
+//! Cells are addressed as `[x, y, z]`: `x` runs along the width, `y` along the height and `z`
+//! picks the level. Every cell keeps its own wall bits, so the wall between two neighbours is
+//! recorded on both sides and carving always updates the pair.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+mod ascii;
+mod cull;
+mod fairness;
+mod hash;
+mod join;
+mod levels;
+mod locks;
+mod mask;
+mod stats;
+mod tags;
+mod topology;
+mod validate;
+mod weave;
+mod wrap;
+
+pub use ascii::ParseError;
+pub use cull::cull_dead_ends;
+pub use fairness::{fairness_report, FairnessReport};
+pub use join::{Axis, JoinError};
+pub use levels::{level_connectivity, LevelInfo};
+pub use locks::Lock;
+pub use mask::Mask;
+pub use stats::{stats, MazeStats};
+pub use tags::{CellTag, TaggedCell};
+pub use topology::{topology, Orthogonal4PerLevel, Orthogonal6, Topology, TOPOLOGIES};
+pub use validate::{validate, validate_layout, Portal, ValidationError};
+pub use wrap::WrapMode;
+
+/// Cell coordinate as `[x, y, z]`.
+pub type Coord = [u32; 3];
+
+/// Wall mask of a cell that has not been carved yet.
+pub const ALL_WALLS: u8 = 0b11_1111;
+
+/// One of the six faces of a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+  /// `+x`
+  East,
+  /// `-x`
+  West,
+  /// `+y`
+  South,
+  /// `-y`
+  North,
+  /// `+z`, towards the next level.
+  Up,
+  /// `-z`, towards the previous level.
+  Down,
+}
+
+impl Direction {
+  pub const ALL: [Direction; 6] = [
+    Direction::East,
+    Direction::West,
+    Direction::South,
+    Direction::North,
+    Direction::Up,
+    Direction::Down,
+  ];
+
+  /// Bit used for this face in a cell's wall mask.
+  pub const fn bit(self) -> u8 {
+    1 << self as u8
+  }
+
+  pub const fn opposite(self) -> Direction {
+    match self {
+      Direction::East => Direction::West,
+      Direction::West => Direction::East,
+      Direction::South => Direction::North,
+      Direction::North => Direction::South,
+      Direction::Up => Direction::Down,
+      Direction::Down => Direction::Up,
+    }
+  }
+
+  /// Unit step in `[x, y, z]`.
+  pub const fn offset(self) -> [i64; 3] {
+    match self {
+      Direction::East => [1, 0, 0],
+      Direction::West => [-1, 0, 0],
+      Direction::South => [0, 1, 0],
+      Direction::North => [0, -1, 0],
+      Direction::Up => [0, 0, 1],
+      Direction::Down => [0, 0, -1],
+    }
+  }
+}
+
+/// A `width x height x depth` block of cells with per-cell wall masks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+  pub(crate) width: u32,
+  pub(crate) height: u32,
+  pub(crate) depth: u32,
+  /// Wall mask per cell, indexed by [`Maze::index`].
+  pub(crate) walls: Vec<u8>,
+  /// Seed the maze was generated from, if it came out of the seeded generator.
+  pub(crate) seed: Option<u64>,
+  pub(crate) entrance: Coord,
+  pub(crate) exit: Coord,
+  /// Footprint limiting which cells exist; `None` means the full block.
+  pub(crate) mask: Option<Mask>,
+  /// Gameplay data keyed by cell; only active cells are ever tagged.
+  pub(crate) tags: HashMap<Coord, CellTag>,
+  /// Active cells where two corridors cross without meeting.
+  pub(crate) crossings: HashSet<Coord>,
+  /// Doors that need a key first, in the order their key bits are numbered.
+  pub(crate) locks: Vec<Lock>,
+  /// Grid edges that join up with the opposite one; see [`Maze::adjacent`].
+  pub(crate) wrap: WrapMode,
+}
+
+impl Maze {
+  /// Creates a maze where every wall is still standing, entered at the bottom `[0, 0, 0]`
+  /// corner and left through the opposite top corner.
+  pub fn new(width: u32, height: u32, depth: u32) -> Maze {
+    let cells = width as usize * height as usize * depth as usize;
+    Maze {
+      width,
+      height,
+      depth,
+      walls: vec![ALL_WALLS; cells],
+      seed: None,
+      entrance: [0, 0, 0],
+      exit: Maze::far_corner(width, height, depth),
+      mask: None,
+      tags: HashMap::new(),
+      crossings: HashSet::new(),
+      locks: Vec::new(),
+      wrap: WrapMode::None,
+    }
+  }
+
+  /// The `[width - 1, height - 1, depth - 1]` corner of a grid.
+  pub(crate) fn far_corner(width: u32, height: u32, depth: u32) -> Coord {
+    [width, height, depth].map(|v| v.saturating_sub(1))
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn depth(&self) -> u32 {
+    self.depth
+  }
+
+  pub fn dimensions(&self) -> (u32, u32, u32) {
+    (self.width, self.height, self.depth)
+  }
+
+  pub fn seed(&self) -> Option<u64> {
+    self.seed
+  }
+
+  pub fn entrance(&self) -> Coord {
+    self.entrance
+  }
+
+  pub fn exit(&self) -> Coord {
+    self.exit
+  }
+
+  pub fn mask(&self) -> Option<&Mask> {
+    self.mask.as_ref()
+  }
+
+  /// Whether `cell` is inside the grid and not masked off.
+  pub fn is_active(&self, cell: Coord) -> bool {
+    self.contains(cell) && self.mask.as_ref().is_none_or(|mask| mask.is_active(cell))
+  }
+
+  pub fn cell_count(&self) -> usize {
+    self.walls.len()
+  }
+
+  pub fn contains(&self, [x, y, z]: Coord) -> bool {
+    x < self.width && y < self.height && z < self.depth
+  }
+
+  /// Flat index of a cell: `x + y * width + z * width * height`.
+  pub fn index(&self, [x, y, z]: Coord) -> usize {
+    let (w, h) = (self.width as usize, self.height as usize);
+    x as usize + y as usize * w + z as usize * w * h
+  }
+
+  /// Inverse of [`Maze::index`].
+  pub fn coord(&self, index: usize) -> Coord {
+    let (w, h) = (self.width as usize, self.height as usize);
+    [
+      (index % w) as u32,
+      (index / w % h) as u32,
+      (index / (w * h)) as u32,
+    ]
+  }
+
+  /// Flat wall masks, one per cell in [`Maze::index`] order.
+  pub fn wall_data(&self) -> &[u8] {
+    &self.walls
+  }
+
+  pub fn walls(&self, cell: Coord) -> u8 {
+    self.walls[self.index(cell)]
+  }
+
+  pub fn has_wall(&self, cell: Coord, dir: Direction) -> bool {
+    self.walls(cell) & dir.bit() != 0
+  }
+
+  /// The cell on the other side of `dir`, if both it and `cell` are active.
+  pub fn neighbor(&self, cell: Coord, dir: Direction) -> Option<Coord> {
+    let next = self.adjacent(cell, dir)?;
+    (self.is_active(cell) && self.is_active(next)).then_some(next)
+  }
+
+  /// The grid cell on the other side of `dir`, ignoring the mask, wrapping around the edges
+  /// [`Maze::wrap`] joins.
+  pub(crate) fn adjacent(&self, cell: Coord, dir: Direction) -> Option<Coord> {
+    self.step(cell, dir, self.wrap)
+  }
+
+  /// Removes the wall between `cell` and its neighbour in `dir` on both sides.
+  ///
+  /// Returns the neighbour, or `None` (leaving the maze untouched) at the grid boundary.
+  pub fn carve(&mut self, cell: Coord, dir: Direction) -> Option<Coord> {
+    let next = self.neighbor(cell, dir)?;
+    let (a, b) = (self.index(cell), self.index(next));
+    self.walls[a] &= !dir.bit();
+    self.walls[b] &= !dir.opposite().bit();
+    Some(next)
+  }
+
+  /// Puts back the wall between `cell` and its neighbour in `dir`; the inverse of
+  /// [`Maze::carve`].
+  pub fn close(&mut self, cell: Coord, dir: Direction) -> Option<Coord> {
+    let next = self.neighbor(cell, dir)?;
+    let (a, b) = (self.index(cell), self.index(next));
+    self.walls[a] |= dir.bit();
+    self.walls[b] |= dir.opposite().bit();
+    Some(next)
+  }
+
+  /// Neighbours reachable from `cell` through an open face.
+  pub fn open_neighbors(&self, cell: Coord) -> impl Iterator<Item = (Direction, Coord)> + '_ {
+    let walls = self.walls(cell);
+    Direction::ALL
+      .into_iter()
+      .filter(move |dir| walls & dir.bit() == 0)
+      .filter_map(move |dir| self.neighbor(cell, dir).map(|next| (dir, next)))
+  }
+}
@@ -0,0 +1,211 @@
+//! Hand-drawn maze templates.
+//!
+//! Each line is a row along `y` and each character a cell along `x`: `#` is solid rock and a
+//! space is open floor. Layers are separated by an empty line and stack upwards, the first
+//! one being level 0.
+
+use std::fmt;
+
+use super::{Direction, Mask, Maze};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+  /// The template has no rows at all.
+  Empty,
+  /// A character other than `#` or a space.
+  UnknownChar {
+    line: usize,
+    column: usize,
+    found: char,
+  },
+  /// A row is shorter or longer than the first one; `column` is where they part ways.
+  RaggedRow {
+    line: usize,
+    column: usize,
+    expected: usize,
+  },
+  /// A layer, starting at `line`, has a different number of rows than the first one.
+  RaggedLayer {
+    line: usize,
+    column: usize,
+    expected: usize,
+    found: usize,
+  },
+  /// Every cell is solid.
+  NoPassage,
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ParseError::Empty => write!(f, "maze template is empty"),
+      ParseError::UnknownChar {
+        line,
+        column,
+        found,
+      } => write!(
+        f,
+        "line {line}, column {column}: unknown character {found:?}, expected '#' or ' '"
+      ),
+      ParseError::RaggedRow {
+        line,
+        column,
+        expected,
+      } => write!(
+        f,
+        "line {line}, column {column}: row should be {expected} cells wide"
+      ),
+      ParseError::RaggedLayer {
+        line,
+        column,
+        expected,
+        found,
+      } => write!(
+        f,
+        "line {line}, column {column}: layer has {found} rows but the first has {expected}"
+      ),
+      ParseError::NoPassage => write!(f, "maze template has no open cells"),
+    }
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Maze {
+  /// Builds a maze from a template as described in this module.
+  ///
+  /// `layers` may hold one layer each or several separated by empty lines; line numbers in a
+  /// [`ParseError`] count through them as if they were joined with empty lines. Solid cells
+  /// are masked off, and every open cell is carved through to the open cells beside, above
+  /// and below it. Entrance and exit go to the first and last open cells in index order.
+  pub fn from_ascii(layers: &[&str]) -> Result<Maze, ParseError> {
+    let text = layers.join("\n\n");
+    // Rows of each layer, with their 1-based line numbers.
+    let mut grid: Vec<Vec<(usize, Vec<bool>)>> = Vec::new();
+    let mut fresh = true;
+    for (number, line) in text.lines().enumerate().map(|(n, l)| (n + 1, l)) {
+      if line.is_empty() {
+        fresh = true;
+        continue;
+      }
+      let row = line
+        .chars()
+        .enumerate()
+        .map(|(column, c)| match c {
+          ' ' => Ok(true),
+          '#' => Ok(false),
+          found => Err(ParseError::UnknownChar {
+            line: number,
+            column: column + 1,
+            found,
+          }),
+        })
+        .collect::<Result<Vec<bool>, _>>()?;
+      if let Some(expected) = grid.first().map(|layer| layer[0].1.len()) {
+        if row.len() != expected {
+          return Err(ParseError::RaggedRow {
+            line: number,
+            column: row.len().min(expected) + 1,
+            expected,
+          });
+        }
+      }
+      if std::mem::take(&mut fresh) {
+        grid.push(Vec::new());
+      }
+      grid.last_mut().expect("pushed above").push((number, row));
+    }
+
+    let Some(first) = grid.first() else {
+      return Err(ParseError::Empty);
+    };
+    let (width, height) = (first[0].1.len(), first.len());
+    if let Some(layer) = grid.iter().find(|layer| layer.len() != height) {
+      return Err(ParseError::RaggedLayer {
+        line: layer[0].0,
+        column: 1,
+        expected: height,
+        found: layer.len(),
+      });
+    }
+    let open: Vec<bool> = grid
+      .iter()
+      .flatten()
+      .flat_map(|(_, row)| row.iter().copied())
+      .collect();
+    if !open.contains(&true) {
+      return Err(ParseError::NoPassage);
+    }
+
+    let mut maze = Maze::new(width as u32, height as u32, grid.len() as u32);
+    if open.contains(&false) {
+      let (width, height, depth) = maze.dimensions();
+      maze.mask = Mask::from_flags(width, height, depth, open.clone());
+    }
+    for (index, _) in open.iter().enumerate().filter(|&(_, &open)| open) {
+      let cell = maze.coord(index);
+      for dir in [Direction::East, Direction::South, Direction::Up] {
+        if maze.neighbor(cell, dir).is_some() {
+          maze.carve(cell, dir);
+        }
+      }
+    }
+    let mut active = open.iter().enumerate().filter(|&(_, &open)| open);
+    let first = active.next().map_or(0, |(index, _)| index);
+    maze.entrance = maze.coord(first);
+    maze.exit = maze.coord(active.next_back().map_or(first, |(index, _)| index));
+    Ok(maze)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver;
+
+  #[test]
+  fn solves_a_parsed_template() {
+    let maze = Maze::from_ascii(&["   #\n## #\n   #"]).unwrap();
+    assert_eq!(maze.dimensions(), (4, 3, 1));
+    assert_eq!((maze.entrance(), maze.exit()), ([0, 0, 0], [2, 2, 0]));
+    assert!(!maze.is_active([3, 1, 0]));
+    let path = solver::bfs(&maze, [0, 0, 0], [0, 2, 0]).unwrap();
+    assert_eq!(path.len(), 7);
+    assert_eq!(path[3], [2, 1, 0]);
+  }
+
+  #[test]
+  fn stacks_layers_upwards() {
+    let maze = Maze::from_ascii(&[" #\n##", "##\n# "]).unwrap();
+    assert_eq!(maze.dimensions(), (2, 2, 2));
+    assert_eq!(
+      solver::bfs(&maze, maze.entrance(), maze.exit()),
+      Err(solver::SolveError::NoPath {
+        start: [0, 0, 0],
+        end: [1, 1, 1],
+      })
+    );
+  }
+
+  #[test]
+  fn reports_where_parsing_failed() {
+    assert_eq!(
+      Maze::from_ascii(&["  \n x"]),
+      Err(ParseError::UnknownChar {
+        line: 2,
+        column: 2,
+        found: 'x'
+      })
+    );
+    // Line numbers run on through the blank line joining the layers.
+    assert_eq!(
+      Maze::from_ascii(&["  ", "  \n "]),
+      Err(ParseError::RaggedRow {
+        line: 4,
+        column: 2,
+        expected: 2
+      })
+    );
+    assert_eq!(Maze::from_ascii(&["##"]), Err(ParseError::NoPassage));
+  }
+}
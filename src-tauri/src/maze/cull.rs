@@ -0,0 +1,101 @@
+//! Thinning a maze down to its skeleton.
+
+use super::Maze;
+
+/// Walls off dead ends, `passes` times over, and returns how many cells were cut off.
+///
+/// Each pass finds every cell with exactly one open neighbour and closes that face, so the
+/// corridors leading to the dead ends of the previous pass shrink by one cell per pass. The
+/// entrance, the exit, lock keys and tagged cells are kept, which leaves every path between
+/// them intact, and so are dead ends opening onto a weave crossing, whose four faces must stay
+/// open. Culling stops early once a pass finds nothing, and clears [`Maze::seed`] if it
+/// changed anything.
+pub fn cull_dead_ends(maze: &mut Maze, passes: u32) -> u32 {
+  let mut culled = 0;
+  for _ in 0..passes {
+    let dead_ends: Vec<_> = (0..maze.cell_count())
+      .map(|index| maze.coord(index))
+      .filter(|&cell| {
+        cell != maze.entrance
+          && cell != maze.exit
+          && !maze.tags.contains_key(&cell)
+          && !maze.locks.iter().any(|lock| lock.key == cell)
+      })
+      .filter_map(|cell| {
+        let mut open = maze.open_neighbors(cell);
+        match (open.next(), open.next()) {
+          (Some((dir, next)), None) if !maze.is_crossing(next) => Some((cell, dir)),
+          _ => None,
+        }
+      })
+      .collect();
+    if dead_ends.is_empty() {
+      break;
+    }
+    for &(cell, dir) in &dead_ends {
+      maze.close(cell, dir);
+    }
+    culled += dead_ends.len() as u32;
+  }
+  if culled > 0 {
+    maze.seed = None;
+  }
+  culled
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::Coord;
+  use crate::solver;
+
+  fn degree(maze: &Maze, cell: Coord) -> usize {
+    maze.open_neighbors(cell).count()
+  }
+
+  #[test]
+  fn one_pass_walls_off_exactly_the_dead_ends() {
+    let mut maze = Maze::generate_with_seed((9, 9, 2), 3, Algorithm::Prim).unwrap();
+    let cells: Vec<Coord> = (0..maze.cell_count())
+      .map(|index| maze.coord(index))
+      .collect();
+    let dead_ends: Vec<Coord> = cells
+      .iter()
+      .copied()
+      .filter(|&cell| cell != maze.entrance() && cell != maze.exit() && degree(&maze, cell) == 1)
+      .collect();
+    let before = maze.clone();
+
+    assert_eq!(cull_dead_ends(&mut maze, 1) as usize, dead_ends.len());
+    assert_eq!(maze.seed(), None);
+    for &cell in &cells {
+      if dead_ends.contains(&cell) {
+        assert_eq!(degree(&maze, cell), 0, "{cell:?}");
+      } else {
+        let lost = degree(&before, cell) - degree(&maze, cell);
+        let culled = before
+          .open_neighbors(cell)
+          .filter(|(_, next)| dead_ends.contains(next))
+          .count();
+        assert_eq!(lost, culled, "{cell:?}");
+      }
+    }
+    assert_eq!(
+      solver::bfs(&maze, maze.entrance(), maze.exit()),
+      solver::bfs(&before, before.entrance(), before.exit())
+    );
+  }
+
+  #[test]
+  fn culling_to_the_end_leaves_only_the_solution() {
+    let mut maze = Maze::generate_with_seed((9, 9, 2), 3, Algorithm::Prim).unwrap();
+    let path = solver::bfs(&maze, maze.entrance(), maze.exit()).unwrap();
+    cull_dead_ends(&mut maze, u32::MAX);
+    let open = (0..maze.cell_count())
+      .filter(|&index| degree(&maze, maze.coord(index)) > 0)
+      .count();
+    assert_eq!(open, path.len());
+    assert_eq!(cull_dead_ends(&mut maze, 1), 0);
+  }
+}
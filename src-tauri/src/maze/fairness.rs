@@ -0,0 +1,87 @@
+//! Path-length comparison between several starting points, for competitive modes.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Maze};
+use crate::solver;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FairnessReport {
+  /// Moves on the shortest path from each start to the goal, in `starts` order, or `None`
+  /// where the goal is unreachable.
+  pub lengths: Vec<Option<u32>>,
+  /// Shortest and longest of the reachable lengths.
+  pub min: Option<u32>,
+  pub max: Option<u32>,
+  /// `max - min`, or `None` when no start reaches the goal.
+  pub spread: Option<u32>,
+}
+
+/// Compares how far each of `starts` is from `goal` over all six directions.
+///
+/// One BFS from the goal covers every start, since passages work both ways.
+pub fn fairness_report(maze: &Maze, starts: &[Coord], goal: Coord) -> FairnessReport {
+  let distances = solver::distance_field(maze, goal);
+  let lengths: Vec<Option<u32>> = starts
+    .iter()
+    .map(|&start| {
+      Some(start)
+        .filter(|&start| maze.contains(start))
+        .map(|start| distances[maze.index(start)])
+        .filter(|&d| d != u32::MAX)
+    })
+    .collect();
+  let min = lengths.iter().flatten().copied().min();
+  let max = lengths.iter().flatten().copied().max();
+  FairnessReport {
+    spread: max.zip(min).map(|(max, min)| max - min),
+    lengths,
+    min,
+    max,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::Direction;
+
+  #[test]
+  fn lengths_match_bfs() {
+    let maze = Maze::generate_with_seed((7, 6, 2), 4, Algorithm::Prim).unwrap();
+    let starts = [[0, 0, 0], [6, 0, 1], [0, 5, 1], [3, 3, 0]];
+    let goal = [6, 5, 1];
+    let report = fairness_report(&maze, &starts, goal);
+    let expected: Vec<u32> = starts
+      .iter()
+      .map(|&start| solver::bfs(&maze, start, goal).unwrap().len() as u32 - 1)
+      .collect();
+    assert_eq!(
+      report.lengths,
+      expected.iter().copied().map(Some).collect::<Vec<_>>()
+    );
+    assert_eq!(report.min, expected.iter().copied().min());
+    assert_eq!(report.max, expected.iter().copied().max());
+    assert_eq!(
+      report.spread,
+      Some(report.max.unwrap() - report.min.unwrap())
+    );
+  }
+
+  #[test]
+  fn unreachable_starts_have_no_length() {
+    let mut maze = Maze::new(3, 1, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    let report = fairness_report(&maze, &[[1, 0, 0], [2, 0, 0], [9, 0, 0]], [0, 0, 0]);
+    assert_eq!(report.lengths, vec![Some(1), None, None]);
+    assert_eq!(
+      (report.min, report.max, report.spread),
+      (Some(1), Some(1), Some(0))
+    );
+
+    let alone = fairness_report(&maze, &[[2, 0, 0]], [0, 0, 0]);
+    assert_eq!((alone.min, alone.max, alone.spread), (None, None, None));
+  }
+}
@@ -0,0 +1,145 @@
+//! Format-independent fingerprints of a maze's layout.
+
+use super::{Coord, Direction, Maze};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Faces hashed per cell; the others belong to the neighbour across them.
+const OWNED: [Direction; 3] = [Direction::East, Direction::South, Direction::Up];
+
+impl Maze {
+  /// 64-bit FNV-1a hash of the dimensions and the layout, stable across runs, platforms and
+  /// file formats.
+  ///
+  /// Each cell contributes one byte: whether its east, south and up faces are walled, whether
+  /// the mask keeps it and whether it is a crossing. Every shared face is read once, from the
+  /// cell west, north or below it, and faces on the grid boundary count as walled, so one-sided
+  /// walls and boundary bits that a format drops do not change the hash.
+  ///
+  /// The edges [`Maze::wrap`] joins and the locks come after the cells, each door named from
+  /// the cell west, north or below it and the locks sorted. Both are skipped when absent, so
+  /// plain mazes keep the hashes they always had. The seed, portals and tags are left out.
+  pub fn content_hash(&self) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut feed = |byte: u8| hash = (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+    for value in [self.width, self.height, self.depth] {
+      value.to_le_bytes().into_iter().for_each(&mut feed);
+    }
+    for index in 0..self.cell_count() {
+      let cell = self.coord(index);
+      let mut byte = 0;
+      for (bit, dir) in OWNED.into_iter().enumerate() {
+        if self.adjacent(cell, dir).is_none() || self.walls[index] & dir.bit() != 0 {
+          byte |= 1 << bit;
+        }
+      }
+      if self.is_active(cell) {
+        byte |= 1 << 3;
+      }
+      if self.is_crossing(cell) {
+        byte |= 1 << 4;
+      }
+      feed(byte);
+    }
+    if !self.wrap.is_none() {
+      feed(b'w');
+      feed(u8::from(self.wrap.wraps(0)) | u8::from(self.wrap.wraps(1)) << 1);
+    }
+    if !self.locks.is_empty() {
+      let mut locks: Vec<(Coord, Coord, u8)> = self
+        .locks
+        .iter()
+        .map(|lock| match self.adjacent(lock.door, lock.dir) {
+          Some(next) if !OWNED.contains(&lock.dir) => (lock.key, next, lock.dir.opposite().bit()),
+          _ => (lock.key, lock.door, lock.dir.bit()),
+        })
+        .collect();
+      locks.sort_unstable();
+      feed(b'l');
+      (locks.len() as u64)
+        .to_le_bytes()
+        .into_iter()
+        .for_each(&mut feed);
+      for (key, door, dir) in locks {
+        for value in key.into_iter().chain(door) {
+          value.to_le_bytes().into_iter().for_each(&mut feed);
+        }
+        feed(dir);
+      }
+    }
+    hash
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::{CellTag, Direction, Lock, WrapMode};
+
+  #[test]
+  fn survives_saving_and_loading() {
+    let maze = Maze::generate_with_seed((6, 5, 3), 13, Algorithm::Kruskal).unwrap();
+    let hash = maze.content_hash();
+    assert_eq!(
+      Maze::from_json(&maze.to_json()).unwrap().content_hash(),
+      hash
+    );
+    assert_eq!(
+      Maze::from_bytes(&maze.to_bytes()).unwrap().content_hash(),
+      hash
+    );
+
+    let mut tagged = maze.clone();
+    tagged.set_tag([1, 1, 1], CellTag::Trap);
+    assert_eq!(tagged.content_hash(), hash);
+  }
+
+  #[test]
+  fn changes_with_a_single_wall() {
+    let maze = Maze::generate_with_seed((6, 5, 3), 13, Algorithm::Kruskal).unwrap();
+    let mut changed = maze.clone();
+    let cell = (0..maze.cell_count())
+      .map(|index| maze.coord(index))
+      .find(|&cell| maze.has_wall(cell, Direction::East) && cell[0] + 1 < 6)
+      .unwrap();
+    changed.carve(cell, Direction::East);
+    assert_ne!(changed.content_hash(), maze.content_hash());
+  }
+
+  #[test]
+  fn changes_with_the_wrap_and_the_locks() {
+    let maze = Maze::generate_with_seed((6, 5, 3), 13, Algorithm::Kruskal).unwrap();
+    let mut wrapped = maze.clone();
+    wrapped.set_wrap(WrapMode::WrapX);
+    let mut both = maze.clone();
+    both.set_wrap(WrapMode::WrapXY);
+    assert_ne!(wrapped.content_hash(), maze.content_hash());
+    assert_ne!(both.content_hash(), wrapped.content_hash());
+
+    let door = [2, 2, 1];
+    let mut locked = maze.clone();
+    assert!(locked.add_lock(Lock {
+      key: [0, 0, 0],
+      door,
+      dir: Direction::East,
+    }));
+    assert_ne!(locked.content_hash(), maze.content_hash());
+    let mut moved = maze.clone();
+    assert!(moved.add_lock(Lock {
+      key: [1, 0, 0],
+      door,
+      dir: Direction::East,
+    }));
+    assert_ne!(moved.content_hash(), locked.content_hash());
+    // The same door named from the other side.
+    let mut mirrored = maze.clone();
+    assert!(mirrored.add_lock(Lock {
+      key: [0, 0, 0],
+      door: [3, 2, 1],
+      dir: Direction::West,
+    }));
+    assert_eq!(mirrored.content_hash(), locked.content_hash());
+  }
+}
@@ -0,0 +1,187 @@
+//! Stitching mazes together side by side.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Direction, Lock, Mask, Maze};
+
+/// Grid axis, `x`, `y` or `z` as in [`Coord`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+  X,
+  Y,
+  Z,
+}
+
+impl Axis {
+  /// Position of this axis in a [`Coord`].
+  pub const fn index(self) -> usize {
+    self as usize
+  }
+
+  /// The face pointing along `+axis`.
+  pub const fn forward(self) -> Direction {
+    match self {
+      Axis::X => Direction::East,
+      Axis::Y => Direction::South,
+      Axis::Z => Direction::Up,
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JoinError {
+  /// The faces to be glued together differ in size.
+  DimensionMismatch {
+    axis: Axis,
+    left: (u32, u32, u32),
+    right: (u32, u32, u32),
+  },
+  /// No active cell on one side of the seam faces an active cell on the other.
+  NoSeam,
+  /// The joined length along the axis does not fit in a `u32`.
+  TooLarge,
+  /// One of the mazes wraps around its edges, which the seam would cut through.
+  Wrapped,
+}
+
+impl fmt::Display for JoinError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      JoinError::DimensionMismatch { axis, left, right } => write!(
+        f,
+        "cannot join a {}x{}x{} maze to a {}x{}x{} one along {axis:?}: the faces differ",
+        left.0, left.1, left.2, right.0, right.1, right.2
+      ),
+      JoinError::NoSeam => write!(f, "no active cells meet across the seam"),
+      JoinError::TooLarge => write!(f, "the joined maze would be too large"),
+      JoinError::Wrapped => write!(f, "cannot join mazes that wrap around their edges"),
+    }
+  }
+}
+
+impl std::error::Error for JoinError {}
+
+impl Maze {
+  /// `self` followed by `other` along `axis`, with one passage opened across the seam.
+  ///
+  /// The passage joins the pair of active cells nearest the middle of the seam, so two
+  /// connected mazes give a connected one. The result keeps the masks, tags, crossings and
+  /// locks of both, with the second maze's key bits numbered after the first's. It enters at
+  /// `self`'s entrance, leaves at `other`'s exit and matches no seed.
+  pub fn join(&self, other: &Maze, axis: Axis) -> Result<Maze, JoinError> {
+    if !self.wrap.is_none() || !other.wrap.is_none() {
+      return Err(JoinError::Wrapped);
+    }
+    let a = axis.index();
+    let left = [self.width, self.height, self.depth];
+    let right = [other.width, other.height, other.depth];
+    if (0..3).any(|i| i != a && left[i] != right[i]) {
+      return Err(JoinError::DimensionMismatch {
+        axis,
+        left: self.dimensions(),
+        right: other.dimensions(),
+      });
+    }
+    let mut dims = left;
+    dims[a] = left[a].checked_add(right[a]).ok_or(JoinError::TooLarge)?;
+    let shift = |mut cell: Coord, by: u32| {
+      cell[a] += by;
+      cell
+    };
+
+    let mut joined = Maze::new(dims[0], dims[1], dims[2]);
+    let mut flags = vec![true; joined.cell_count()];
+    for (maze, by) in [(self, 0), (other, left[a])] {
+      for index in 0..maze.cell_count() {
+        let cell = maze.coord(index);
+        let target = joined.index(shift(cell, by));
+        joined.walls[target] = maze.walls[index];
+        flags[target] = maze.is_active(cell);
+      }
+      for (&cell, tag) in &maze.tags {
+        joined.tags.insert(shift(cell, by), tag.clone());
+      }
+      joined
+        .crossings
+        .extend(maze.crossings.iter().map(|&cell| shift(cell, by)));
+      joined.locks.extend(maze.locks.iter().map(|lock| Lock {
+        key: shift(lock.key, by),
+        door: shift(lock.door, by),
+        dir: lock.dir,
+      }));
+    }
+    if self.mask.is_some() || other.mask.is_some() {
+      joined.mask = Mask::from_flags(dims[0], dims[1], dims[2], flags);
+    }
+    joined.entrance = self.entrance;
+    joined.exit = shift(other.exit, left[a]);
+
+    let dir = axis.forward();
+    // Twice the offset from the centre of the seam, summed over the other two axes.
+    let off_centre = |cell: Coord| {
+      (0..3)
+        .filter(|&i| i != a)
+        .map(|i| (2 * i64::from(cell[i]) + 1 - i64::from(dims[i])).abs())
+        .sum::<i64>()
+    };
+    let seam = (0..joined.cell_count())
+      .map(|index| joined.coord(index))
+      .filter(|cell| cell[a] == left[a] - 1 && !joined.is_crossing(*cell))
+      .filter(|&cell| {
+        joined
+          .neighbor(cell, dir)
+          .is_some_and(|next| !joined.is_crossing(next))
+      })
+      .min_by_key(|&cell| off_centre(cell))
+      .ok_or(JoinError::NoSeam)?;
+    joined.carve(seam, dir);
+    Ok(joined)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::solver;
+
+  #[test]
+  fn joins_along_every_axis() {
+    let first = Maze::generate_with_seed((4, 3, 2), 1, Algorithm::Prim).unwrap();
+    let second = Maze::generate_with_seed((4, 3, 2), 2, Algorithm::Kruskal).unwrap();
+    for (axis, dims) in [
+      (Axis::X, (8, 3, 2)),
+      (Axis::Y, (4, 6, 2)),
+      (Axis::Z, (4, 3, 4)),
+    ] {
+      let joined = first.join(&second, axis).unwrap();
+      assert_eq!(joined.dimensions(), dims);
+      assert_eq!(joined.entrance(), first.entrance());
+      assert_eq!(joined.seed(), None);
+      assert!(
+        solver::bfs(&joined, joined.entrance(), joined.exit()).is_ok(),
+        "{axis:?}"
+      );
+      let distances = solver::distance_field(&joined, joined.entrance());
+      assert!(distances.iter().all(|&distance| distance != u32::MAX));
+    }
+  }
+
+  #[test]
+  fn rejects_faces_of_different_sizes() {
+    let first = Maze::generate_with_seed((4, 3, 2), 1, Algorithm::Prim).unwrap();
+    let second = Maze::generate_with_seed((4, 5, 2), 2, Algorithm::Prim).unwrap();
+    assert!(first.join(&second, Axis::Y).is_ok());
+    assert_eq!(
+      first.join(&second, Axis::X),
+      Err(JoinError::DimensionMismatch {
+        axis: Axis::X,
+        left: (4, 3, 2),
+        right: (4, 5, 2)
+      })
+    );
+  }
+}
@@ -0,0 +1,103 @@
+//! Per-level summary of how the floors of a maze join up.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Direction, Maze};
+use crate::solver;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelInfo {
+  pub level: u32,
+  /// Open faces leading to an active cell on the level above.
+  pub links_up: u32,
+  /// Open faces leading to an active cell on the level below; the level below's `links_up`
+  /// whenever the walls are two-sided.
+  pub links_down: u32,
+  /// Whether any cell on the level can be reached from the entrance.
+  pub reachable: bool,
+}
+
+/// One [`LevelInfo`] per level, bottom first, to catch floors hanging on a single stair or
+/// cut off altogether.
+pub fn level_connectivity(maze: &Maze) -> Vec<LevelInfo> {
+  let distances = solver::distance_field(maze, maze.entrance());
+  let mut levels: Vec<LevelInfo> = (0..maze.depth())
+    .map(|level| LevelInfo {
+      level,
+      links_up: 0,
+      links_down: 0,
+      reachable: false,
+    })
+    .collect();
+  for (index, &distance) in distances.iter().enumerate() {
+    let cell = maze.coord(index);
+    if !maze.is_active(cell) {
+      continue;
+    }
+    let info = &mut levels[cell[2] as usize];
+    for (dir, _) in maze.open_neighbors(cell) {
+      match dir {
+        Direction::Up => info.links_up += 1,
+        Direction::Down => info.links_down += 1,
+        _ => {}
+      }
+    }
+    info.reachable |= distance != u32::MAX;
+  }
+  levels
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::Mask;
+
+  #[test]
+  fn flags_a_level_nothing_leads_to() {
+    let mut maze = Maze::new(2, 2, 3);
+    for z in 0..3 {
+      maze.carve([0, 0, z], Direction::East);
+      maze.carve([0, 0, z], Direction::South);
+      maze.carve([1, 0, z], Direction::South);
+    }
+    maze.carve([1, 1, 0], Direction::Up);
+    let levels = level_connectivity(&maze);
+    let summary: Vec<(u32, u32, bool)> = levels
+      .iter()
+      .map(|info| (info.links_up, info.links_down, info.reachable))
+      .collect();
+    assert_eq!(summary, [(1, 0, true), (0, 1, true), (0, 0, false)]);
+  }
+
+  #[test]
+  fn links_agree_between_neighbouring_levels() {
+    let maze = Maze::generate_with_seed((6, 6, 4), 2, Algorithm::Kruskal).unwrap();
+    let levels = level_connectivity(&maze);
+    assert!(levels.iter().all(|info| info.reachable));
+    assert!(levels
+      .windows(2)
+      .all(|pair| pair[0].links_up == pair[1].links_down && pair[0].links_up > 0));
+    assert_eq!(levels[3].links_up, 0);
+  }
+
+  #[test]
+  fn faces_open_onto_nothing_are_not_links() {
+    let mut maze = Maze::new(2, 1, 2);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([0, 0, 1], Direction::East);
+    maze.carve([0, 0, 0], Direction::Up);
+    // One-sided openings through the roof, the floor and into a masked-off cell.
+    let top = maze.index([0, 0, 1]);
+    maze.walls[top] &= !Direction::Up.bit();
+    let bottom = maze.index([1, 0, 0]);
+    maze.walls[bottom] &= !(Direction::Down.bit() | Direction::Up.bit());
+    maze.mask = Some(Mask::from_flags(2, 1, 2, vec![true, true, true, false]).unwrap());
+    let links: Vec<(u32, u32)> = level_connectivity(&maze)
+      .iter()
+      .map(|info| (info.links_up, info.links_down))
+      .collect();
+    assert_eq!(links, [(1, 0), (0, 1)]);
+  }
+}
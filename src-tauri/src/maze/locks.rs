@@ -0,0 +1,49 @@
+//! Locked passages and the keys that open them.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Direction, Maze};
+
+/// A passage that stays shut until its key cell has been visited.
+///
+/// The door is the face `dir` of `door`, shared with the neighbour beyond it, and locks it from
+/// both sides. Locks sit on top of the walls: a plain search still walks through them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lock {
+  pub key: Coord,
+  pub door: Coord,
+  pub dir: Direction,
+}
+
+impl Maze {
+  /// Adds `lock`. Returns `false`, leaving the maze untouched, if its key or either side of its
+  /// door is outside the grid or masked off.
+  pub fn add_lock(&mut self, lock: Lock) -> bool {
+    let valid = self.is_active(lock.key) && self.neighbor(lock.door, lock.dir).is_some();
+    if valid {
+      self.locks.push(lock);
+    }
+    valid
+  }
+
+  /// Every lock, in the order they were added; a key search treats lock `i` as bit `i`.
+  pub fn locks(&self) -> &[Lock] {
+    &self.locks
+  }
+
+  pub fn clear_locks(&mut self) {
+    self.locks.clear();
+  }
+
+  /// Re-adds loaded locks, returning those dropped because their cells no longer exist.
+  pub(crate) fn restore_locks(&mut self, locks: impl IntoIterator<Item = Lock>) -> Vec<Lock> {
+    let mut dropped = Vec::new();
+    for lock in locks {
+      if !self.add_lock(lock) {
+        dropped.push(lock);
+      }
+    }
+    dropped
+  }
+}
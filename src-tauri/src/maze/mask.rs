@@ -0,0 +1,74 @@
+//! Footprints for irregular mazes.
+
+use serde::{Deserialize, Serialize};
+
+use super::Coord;
+
+/// Which cells of a `width x height x depth` block take part in the maze.
+///
+/// Inactive cells are never carved and are invisible to neighbour expansion, so generators and
+/// solvers treat them like space outside the grid.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mask {
+  width: u32,
+  height: u32,
+  depth: u32,
+  /// One flag per cell in [`super::Maze::index`] order.
+  active: Vec<bool>,
+}
+
+impl Mask {
+  /// A mask with every cell active.
+  pub fn new(width: u32, height: u32, depth: u32) -> Mask {
+    let cells = width as usize * height as usize * depth as usize;
+    Mask {
+      width,
+      height,
+      depth,
+      active: vec![true; cells],
+    }
+  }
+
+  /// Builds a mask from flags in [`super::Maze::index`] order, or `None` if the count is off.
+  pub fn from_flags(width: u32, height: u32, depth: u32, active: Vec<bool>) -> Option<Mask> {
+    let cells = width as usize * height as usize * depth as usize;
+    (active.len() == cells).then_some(Mask {
+      width,
+      height,
+      depth,
+      active,
+    })
+  }
+
+  pub fn dimensions(&self) -> (u32, u32, u32) {
+    (self.width, self.height, self.depth)
+  }
+
+  /// Flags in [`super::Maze::index`] order.
+  pub fn flags(&self) -> &[bool] {
+    &self.active
+  }
+
+  pub fn active_count(&self) -> usize {
+    self.active.iter().filter(|&&active| active).count()
+  }
+
+  /// Whether `cell` is inside the block and active.
+  pub fn is_active(&self, cell: Coord) -> bool {
+    self.index(cell).is_some_and(|index| self.active[index])
+  }
+
+  /// Marks `cell` active or inactive; out-of-range cells are ignored.
+  pub fn set_active(&mut self, cell: Coord, active: bool) {
+    if let Some(index) = self.index(cell) {
+      self.active[index] = active;
+    }
+  }
+
+  fn index(&self, [x, y, z]: Coord) -> Option<usize> {
+    let (w, h) = (self.width as usize, self.height as usize);
+    (x < self.width && y < self.height && z < self.depth)
+      .then(|| x as usize + y as usize * w + z as usize * w * h)
+  }
+}
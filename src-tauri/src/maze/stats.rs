@@ -0,0 +1,148 @@
+//! Difficulty metrics for level-select screens.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Maze};
+use crate::solver;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MazeStats {
+  /// Moves on the shortest entrance-to-exit path, or `None` if the exit is unreachable.
+  pub solution_length: Option<u32>,
+  /// Cells with exactly one open face.
+  pub dead_ends: u32,
+  /// Cells with three or more open faces.
+  pub junctions: u32,
+  /// Average number of ways onward (open faces minus the one you came through) over every
+  /// cell that is not a dead end; `1.0` means a single corridor.
+  pub branching_factor: f64,
+  /// Longest shortest path, in moves, within the entrance's region; `0` when the entrance is
+  /// outside the grid or masked off.
+  pub diameter: u32,
+  /// Cells at either end of the [`MazeStats::diameter`] path.
+  pub diameter_ends: [Coord; 2],
+}
+
+/// Computes [`MazeStats`] over all six directions, so stairs count like any other passage.
+///
+/// The diameter comes from a double BFS (farthest cell from the entrance, then farthest cell
+/// from that one), which is exact for perfect mazes and a close lower bound once loops exist.
+pub fn stats(maze: &Maze) -> MazeStats {
+  let mut dead_ends = 0;
+  let mut junctions = 0;
+  let mut onward = 0u64;
+  let mut passable = 0u64;
+  for index in 0..maze.cell_count() {
+    let degree = maze.open_neighbors(maze.coord(index)).count() as u64;
+    match degree {
+      0 => {}
+      1 => dead_ends += 1,
+      _ => {
+        junctions += u32::from(degree >= 3);
+        onward += degree - 1;
+        passable += 1;
+      }
+    }
+  }
+
+  let from_entrance = solver::distance_field(maze, maze.entrance());
+  // Hand-edited mazes may put the exit outside the grid, where it cannot be reached.
+  let exit = maze.exit();
+  let solution_length = maze
+    .is_active(exit)
+    .then(|| from_entrance[maze.index(exit)])
+    .filter(|&d| d != u32::MAX);
+  let (first, second, diameter) = if maze.is_active(maze.entrance()) {
+    let first = solver::farthest(maze, &from_entrance);
+    let from_first = solver::distance_field(maze, first);
+    let second = solver::farthest(maze, &from_first);
+    (first, second, from_first[maze.index(second)])
+  } else {
+    (maze.entrance(), maze.entrance(), 0)
+  };
+
+  MazeStats {
+    solution_length,
+    dead_ends,
+    junctions,
+    branching_factor: if passable == 0 {
+      0.0
+    } else {
+      onward as f64 / passable as f64
+    },
+    diameter,
+    diameter_ends: [first, second],
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::maze::Direction;
+
+  #[test]
+  fn an_exit_outside_the_grid_has_no_solution() {
+    let mut maze = Maze::new(3, 3, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([1, 0, 0], Direction::East);
+    maze.exit = [2, 0, 0];
+    assert_eq!(stats(&maze).solution_length, Some(2));
+    // Would alias cell [2, 1, 0] if taken as a flat index.
+    maze.exit = [5, 0, 0];
+    assert_eq!(stats(&maze).solution_length, None);
+    maze.exit = [100, 0, 0];
+    assert_eq!(stats(&maze).solution_length, None);
+  }
+
+  #[test]
+  fn counts_dead_ends_and_junctions_of_a_plus() {
+    let mut maze = Maze::new(3, 3, 1);
+    for dir in [
+      Direction::East,
+      Direction::West,
+      Direction::South,
+      Direction::North,
+    ] {
+      maze.carve([1, 1, 0], dir);
+    }
+    maze.entrance = [1, 0, 0];
+    let stats = stats(&maze);
+    assert_eq!((stats.dead_ends, stats.junctions), (4, 1));
+    // The centre has three ways onward.
+    assert_eq!(stats.branching_factor, 3.0);
+    assert_eq!(stats.diameter, 2);
+  }
+
+  #[test]
+  fn the_diameter_climbs_between_levels() {
+    // A corridor east along level 0, up, then back west along level 1.
+    let mut maze = Maze::new(3, 1, 2);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([1, 0, 0], Direction::East);
+    maze.carve([2, 0, 0], Direction::Up);
+    maze.carve([2, 0, 1], Direction::West);
+    maze.carve([1, 0, 1], Direction::West);
+    let stats = stats(&maze);
+    assert_eq!(stats.dead_ends, 2);
+    assert_eq!(stats.junctions, 0);
+    assert_eq!(stats.diameter, 5);
+    let mut ends = stats.diameter_ends;
+    ends.sort();
+    assert_eq!(ends, [[0, 0, 0], [0, 0, 1]]);
+  }
+
+  #[test]
+  fn a_masked_entrance_has_no_diameter() {
+    let flags = vec![false, true, true];
+    let mut maze = Maze::new(3, 1, 1);
+    maze.mask = crate::maze::Mask::from_flags(3, 1, 1, flags);
+    maze.carve([1, 0, 0], Direction::East);
+    maze.entrance = [0, 0, 0];
+    let masked = stats(&maze);
+    assert_eq!(masked.diameter, 0);
+    assert_eq!(masked.diameter_ends, [[0, 0, 0], [0, 0, 0]]);
+    maze.entrance = [9, 0, 0];
+    assert_eq!(stats(&maze).diameter, 0);
+  }
+}
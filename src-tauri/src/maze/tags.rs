@@ -0,0 +1,134 @@
+//! Gameplay tags attached to individual cells.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Maze};
+
+/// Game data carried by a cell.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellTag {
+  Treasure,
+  Trap,
+  Spawn,
+  /// Anything the game defines itself.
+  Custom(String),
+}
+
+/// A tag and the cell it sits on, as stored in files and sent to the frontend.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaggedCell {
+  pub cell: Coord,
+  pub tag: CellTag,
+}
+
+impl Maze {
+  /// Tags `cell`, replacing any previous tag. Returns `false`, leaving the maze untouched, if
+  /// the cell is outside the grid or masked off.
+  pub fn set_tag(&mut self, cell: Coord, tag: CellTag) -> bool {
+    let active = self.is_active(cell);
+    if active {
+      self.tags.insert(cell, tag);
+    }
+    active
+  }
+
+  pub fn get_tag(&self, cell: Coord) -> Option<&CellTag> {
+    self.tags.get(&cell)
+  }
+
+  /// Removes and returns the tag on `cell`.
+  pub fn clear_tag(&mut self, cell: Coord) -> Option<CellTag> {
+    self.tags.remove(&cell)
+  }
+
+  /// Every tag, ordered by [`Maze::index`] so serialized output is stable.
+  pub fn tagged_cells(&self) -> Vec<TaggedCell> {
+    let mut tagged: Vec<TaggedCell> = self
+      .tags
+      .iter()
+      .map(|(&cell, tag)| TaggedCell {
+        cell,
+        tag: tag.clone(),
+      })
+      .collect();
+    tagged.sort_by_key(|tagged| self.index(tagged.cell));
+    tagged
+  }
+
+  /// Re-attaches loaded tags, returning those dropped because their cell no longer exists.
+  pub(crate) fn restore_tags(
+    &mut self,
+    tagged: impl IntoIterator<Item = TaggedCell>,
+  ) -> Vec<TaggedCell> {
+    let mut dropped = Vec::new();
+    for TaggedCell { cell, tag } in tagged {
+      if self.is_active(cell) {
+        self.tags.insert(cell, tag);
+      } else {
+        dropped.push(TaggedCell { cell, tag });
+      }
+    }
+    dropped
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::ValidationError;
+
+  fn tagged() -> Maze {
+    let mut maze = Maze::generate_with_seed((5, 4, 2), 3, Algorithm::Prim).unwrap();
+    assert!(maze.set_tag([1, 2, 0], CellTag::Treasure));
+    assert!(maze.set_tag([4, 3, 1], CellTag::Custom("altar".to_owned())));
+    maze
+  }
+
+  #[test]
+  fn tags_survive_json_and_binary() {
+    let maze = tagged();
+    let from_json = Maze::from_json(&maze.to_json()).unwrap();
+    let from_bytes = Maze::from_bytes(&maze.to_bytes()).unwrap();
+    assert_eq!(from_json.tagged_cells(), maze.tagged_cells());
+    assert_eq!(from_bytes.tagged_cells(), maze.tagged_cells());
+  }
+
+  #[test]
+  fn tags_outside_the_grid_are_dropped() {
+    let mut maze = Maze::new(3, 3, 1);
+    let stray = TaggedCell {
+      cell: [3, 0, 0],
+      tag: CellTag::Trap,
+    };
+    let kept = TaggedCell {
+      cell: [2, 2, 0],
+      tag: CellTag::Spawn,
+    };
+    assert_eq!(
+      maze.restore_tags([stray.clone(), kept.clone()]),
+      vec![stray]
+    );
+    assert_eq!(maze.tagged_cells(), vec![kept]);
+  }
+
+  #[test]
+  fn loading_reports_tags_outside_the_grid() {
+    let mut maze = tagged();
+    let mut json: serde_json::Value = serde_json::from_str(&maze.to_json()).unwrap();
+    json["tags"][0]["cell"] = serde_json::json!([5, 0, 0]);
+    let (loaded, dropped) = Maze::from_json_with_warnings(&json.to_string()).unwrap();
+    assert_eq!(dropped, [ValidationError::DroppedTag { cell: [5, 0, 0] }]);
+    assert_eq!(loaded.tagged_cells().len(), 1);
+    assert_eq!(
+      Maze::from_json(&json.to_string()).unwrap().tagged_cells(),
+      loaded.tagged_cells()
+    );
+
+    maze.tags.insert([0, 9, 0], CellTag::Trap);
+    let (loaded, dropped) = Maze::from_bytes_with_warnings(&maze.to_bytes()).unwrap();
+    assert_eq!(dropped, [ValidationError::DroppedTag { cell: [0, 9, 0] }]);
+    assert_eq!(loaded.tagged_cells(), tagged().tagged_cells());
+  }
+}
@@ -0,0 +1,110 @@
+//! Which faces count as moves, shared by the generators and solvers.
+//!
+//! Walls only exist on the six faces of a cell, so every topology is a subset of them;
+//! diagonal steps would need walls the grid does not record.
+
+use super::{Coord, Direction, Maze};
+
+/// Faces that may be carved and walked through.
+///
+/// `Sync` so a borrowed topology can travel with a background generation.
+pub trait Topology: Sync {
+  /// Stable id used by commands, e.g. `"orthogonal6"`.
+  fn id(&self) -> &'static str;
+
+  fn directions(&self) -> &'static [Direction];
+
+  /// Whether moving up or down a level is allowed at all.
+  fn is_vertical(&self) -> bool {
+    self.directions().contains(&Direction::Up)
+  }
+}
+
+/// All six faces: levels connect wherever a passage leads up or down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orthogonal6;
+
+impl Topology for Orthogonal6 {
+  fn id(&self) -> &'static str {
+    "orthogonal6"
+  }
+
+  fn directions(&self) -> &'static [Direction] {
+    &Direction::ALL
+  }
+}
+
+/// The four planar faces only, so every level is its own maze.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orthogonal4PerLevel;
+
+impl Topology for Orthogonal4PerLevel {
+  fn id(&self) -> &'static str {
+    "orthogonal4_per_level"
+  }
+
+  fn directions(&self) -> &'static [Direction] {
+    &[
+      Direction::East,
+      Direction::West,
+      Direction::South,
+      Direction::North,
+    ]
+  }
+}
+
+/// Every built-in topology.
+pub const TOPOLOGIES: [&dyn Topology; 2] = [&Orthogonal6, &Orthogonal4PerLevel];
+
+/// Looks up a built-in topology by [`Topology::id`].
+pub fn topology(id: &str) -> Option<&'static dyn Topology> {
+  TOPOLOGIES.into_iter().find(|topology| topology.id() == id)
+}
+
+impl Maze {
+  /// Neighbours reachable from `cell` through open faces that `topology` allows.
+  pub fn open_moves<'a>(
+    &'a self,
+    topology: &'a dyn Topology,
+    cell: Coord,
+  ) -> impl Iterator<Item = (Direction, Coord)> + 'a {
+    self
+      .open_neighbors(cell)
+      .filter(move |(dir, _)| topology.directions().contains(dir))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::solver;
+
+  #[test]
+  fn per_level_search_never_changes_level() {
+    let maze = Maze::generate_with_seed((6, 6, 3), 5, Algorithm::Kruskal).unwrap();
+    let (start, end) = ([0, 0, 1], [5, 5, 2]);
+    let everywhere = solver::explore(&maze, start, end).unwrap();
+    assert!(everywhere.visited.iter().any(|cell| cell[2] != 1));
+
+    let found = solver::explore_in(&maze, start, end, &Orthogonal4PerLevel).unwrap();
+    assert_eq!(found.path, None);
+    assert!(found.visited.iter().all(|cell| cell[2] == 1));
+    let path = solver::bfs_in(
+      &maze,
+      start,
+      *found.visited.last().unwrap(),
+      &Orthogonal4PerLevel,
+    )
+    .unwrap();
+    assert!(path.iter().all(|cell| cell[2] == 1));
+  }
+
+  #[test]
+  fn looks_up_topologies_by_id() {
+    for topology in TOPOLOGIES {
+      assert_eq!(super::topology(topology.id()).unwrap().id(), topology.id());
+    }
+    assert!(super::topology("hex").is_none());
+  }
+}
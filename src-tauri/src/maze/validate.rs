@@ -0,0 +1,258 @@
+//! Consistency checks for mazes that did not come straight from the generator.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use super::{Coord, Direction, Lock, Maze, TaggedCell};
+
+/// Which end of the maze a [`ValidationError`] is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Portal {
+  Entrance,
+  Exit,
+}
+
+/// One problem found by [`validate`], serialized with its `kind` for the frontend.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(
+  tag = "kind",
+  rename_all = "snake_case",
+  rename_all_fields = "camelCase"
+)]
+pub enum ValidationError {
+  /// A dimension is zero, or the cell count does not fit in memory at all.
+  ZeroDimension { width: u32, height: u32, depth: u32 },
+  /// The wall grid holds a different number of cells than the dimensions declare.
+  GridLength { expected: usize, found: usize },
+  /// The mask holds a different number of flags than the dimensions declare.
+  MaskLength { expected: usize, found: usize },
+  /// The face between `cell` and its neighbour in `dir` is walled on one side only.
+  OneSidedWall { cell: Coord, dir: Direction },
+  /// `cell` has an opening onto the outside of the grid.
+  OpenBoundary { cell: Coord, dir: Direction },
+  /// The entrance or exit is outside the grid or masked off.
+  PortalOutOfBounds { portal: Portal, cell: Coord },
+  /// The entrance or exit has no open face at all.
+  PortalWalledIn { portal: Portal, cell: Coord },
+  /// A tag sits outside the grid or on a masked cell, so loading dropped it.
+  DroppedTag { cell: Coord },
+  /// A weave crossing sits outside the grid or on a masked cell, so loading dropped it.
+  DroppedCrossing { cell: Coord },
+  /// A lock's key or door leaves the grid or touches a masked cell, so loading dropped it.
+  DroppedLock { lock: Lock },
+}
+
+impl fmt::Display for ValidationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ValidationError::ZeroDimension {
+        width,
+        height,
+        depth,
+      } => write!(f, "invalid maze dimensions {width}x{height}x{depth}"),
+      ValidationError::GridLength { expected, found } => write!(
+        f,
+        "maze declares {expected} cells but carries {found} wall masks"
+      ),
+      ValidationError::MaskLength { expected, found } => write!(
+        f,
+        "maze declares {expected} cells but carries {found} mask flags"
+      ),
+      ValidationError::OneSidedWall { cell, dir } => write!(
+        f,
+        "the {dir:?} wall of {cell:?} is missing on the other side"
+      ),
+      ValidationError::OpenBoundary { cell, dir } => {
+        write!(f, "{cell:?} is open to the {dir:?} outside the grid")
+      }
+      ValidationError::PortalOutOfBounds { portal, cell } => {
+        write!(f, "{portal:?} {cell:?} is outside the maze")
+      }
+      ValidationError::PortalWalledIn { portal, cell } => {
+        write!(f, "{portal:?} {cell:?} is walled in on every side")
+      }
+      ValidationError::DroppedTag { cell } => {
+        write!(f, "the tag on {cell:?} is outside the maze and was dropped")
+      }
+      ValidationError::DroppedCrossing { cell } => {
+        write!(
+          f,
+          "the crossing at {cell:?} is outside the maze and was dropped"
+        )
+      }
+      ValidationError::DroppedLock { lock } => write!(
+        f,
+        "the lock on the {:?} door of {:?} is outside the maze and was dropped",
+        lock.dir, lock.door
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Maze {
+  /// Re-attaches loaded tags, crossings and locks, reporting every one dropped for sitting
+  /// outside the grid or on a masked cell.
+  #[must_use = "dropped entries should reach the user"]
+  pub(crate) fn restore_annotations(
+    &mut self,
+    tags: Vec<TaggedCell>,
+    crossings: Vec<Coord>,
+    locks: Vec<Lock>,
+  ) -> Vec<ValidationError> {
+    let tags = self
+      .restore_tags(tags)
+      .into_iter()
+      .map(|tagged| ValidationError::DroppedTag { cell: tagged.cell });
+    let crossings = self
+      .restore_crossings(crossings)
+      .into_iter()
+      .map(|cell| ValidationError::DroppedCrossing { cell });
+    let locks = self
+      .restore_locks(locks)
+      .into_iter()
+      .map(|lock| ValidationError::DroppedLock { lock });
+    tags.chain(crossings).chain(locks).collect()
+  }
+}
+
+/// Checks that `walls` wall masks and `mask` flags fit a maze of `dims`.
+///
+/// This is the part of [`validate`] that can run before a [`Maze`] exists, on raw input.
+pub fn validate_layout(
+  dims: (u32, u32, u32),
+  walls: usize,
+  mask: Option<usize>,
+) -> Result<(), Vec<ValidationError>> {
+  let (width, height, depth) = dims;
+  // The dimensions may come straight from a request, so the product must not overflow.
+  let Some(expected) = (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|cells| cells.checked_mul(depth as usize))
+    .filter(|&cells| cells > 0)
+  else {
+    return Err(vec![ValidationError::ZeroDimension {
+      width,
+      height,
+      depth,
+    }]);
+  };
+  let mut errors = Vec::new();
+  if walls != expected {
+    errors.push(ValidationError::GridLength {
+      expected,
+      found: walls,
+    });
+  }
+  if let Some(found) = mask.filter(|&found| found != expected) {
+    errors.push(ValidationError::MaskLength { expected, found });
+  }
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+/// Lists everything wrong with `maze`: grid layout, walls recorded on one side of a face only,
+/// openings onto the outside, and an entrance or exit that is out of bounds or walled in.
+///
+/// One-sided walls are reported once, from the cell west, north or below the face.
+pub fn validate(maze: &Maze) -> Result<(), Vec<ValidationError>> {
+  let mask = maze.mask.as_ref().map(|mask| mask.flags().len());
+  validate_layout(maze.dimensions(), maze.walls.len(), mask)?;
+
+  let mut errors = Vec::new();
+  for index in 0..maze.cell_count() {
+    let cell = maze.coord(index);
+    for dir in Direction::ALL {
+      let walled = maze.has_wall(cell, dir);
+      match maze.adjacent(cell, dir) {
+        None if !walled => errors.push(ValidationError::OpenBoundary { cell, dir }),
+        None => {}
+        Some(next) => {
+          let forward = matches!(dir, Direction::East | Direction::South | Direction::Up);
+          if forward && walled != maze.has_wall(next, dir.opposite()) {
+            errors.push(ValidationError::OneSidedWall { cell, dir });
+          }
+        }
+      }
+    }
+  }
+
+  // A lone cell has nothing to open onto.
+  let cells = maze
+    .mask
+    .as_ref()
+    .map_or(maze.cell_count(), |mask| mask.active_count());
+  for (portal, cell) in [(Portal::Entrance, maze.entrance), (Portal::Exit, maze.exit)] {
+    if !maze.is_active(cell) {
+      errors.push(ValidationError::PortalOutOfBounds { portal, cell });
+    } else if cells > 1 && Direction::ALL.iter().all(|&dir| maze.has_wall(cell, dir)) {
+      errors.push(ValidationError::PortalWalledIn { portal, cell });
+    }
+  }
+
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+
+  #[test]
+  fn a_generated_maze_is_sound() {
+    let maze = Maze::generate_with_seed((5, 4, 2), 6, Algorithm::RecursiveBacktracker).unwrap();
+    assert_eq!(validate(&maze), Ok(()));
+  }
+
+  #[test]
+  fn finds_a_one_sided_wall() {
+    let mut maze = Maze::generate_with_seed((5, 4, 2), 6, Algorithm::RecursiveBacktracker).unwrap();
+    let cell = (0..maze.cell_count())
+      .map(|index| maze.coord(index))
+      .find(|&cell| !maze.has_wall(cell, Direction::East))
+      .unwrap();
+    let index = maze.index(cell);
+    maze.walls[index] |= Direction::East.bit();
+    assert_eq!(
+      validate(&maze),
+      Err(vec![ValidationError::OneSidedWall {
+        cell,
+        dir: Direction::East
+      }])
+    );
+  }
+
+  #[test]
+  fn finds_a_grid_length_mismatch() {
+    assert_eq!(
+      validate_layout((3, 3, 2), 17, None),
+      Err(vec![ValidationError::GridLength {
+        expected: 18,
+        found: 17
+      }])
+    );
+  }
+
+  #[test]
+  fn rejects_dimensions_whose_product_overflows() {
+    let dims = (u32::MAX, u32::MAX, u32::MAX);
+    assert_eq!(
+      validate_layout(dims, 0, Some(0)),
+      Err(vec![ValidationError::ZeroDimension {
+        width: u32::MAX,
+        height: u32::MAX,
+        depth: u32::MAX
+      }])
+    );
+  }
+}
@@ -0,0 +1,97 @@
+//! Weave crossings, where one corridor bridges over another.
+//!
+//! A crossing cell is open on all four planar faces but joins nothing: the east-west corridor
+//! passes over the north-south one, so a path through it keeps going straight. Its up and
+//! down faces are never walked.
+
+use super::{Coord, Maze};
+
+impl Maze {
+  /// Marks `cell` as a crossing. Returns `false`, leaving the maze untouched, if the cell is
+  /// outside the grid or masked off.
+  pub fn add_crossing(&mut self, cell: Coord) -> bool {
+    let active = self.is_active(cell);
+    if active {
+      self.crossings.insert(cell);
+    }
+    active
+  }
+
+  pub fn is_crossing(&self, cell: Coord) -> bool {
+    self.crossings.contains(&cell)
+  }
+
+  /// Turns a crossing back into a plain cell, where every open face meets. Returns whether it
+  /// was one.
+  pub fn remove_crossing(&mut self, cell: Coord) -> bool {
+    self.crossings.remove(&cell)
+  }
+
+  /// Every crossing, ordered by [`Maze::index`] so serialized output is stable.
+  pub fn crossings(&self) -> Vec<Coord> {
+    let mut crossings: Vec<Coord> = self.crossings.iter().copied().collect();
+    crossings.sort_by_key(|&cell| self.index(cell));
+    crossings
+  }
+
+  /// Re-marks loaded crossings, returning those dropped because their cell no longer exists.
+  pub(crate) fn restore_crossings(
+    &mut self,
+    crossings: impl IntoIterator<Item = Coord>,
+  ) -> Vec<Coord> {
+    let mut dropped = Vec::new();
+    for cell in crossings {
+      if !self.add_crossing(cell) {
+        dropped.push(cell);
+      }
+    }
+    dropped
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::maze::Direction;
+  use crate::solver::{self, SolveError};
+
+  /// A 3x3 plus sign whose centre is a crossing.
+  fn plus() -> Maze {
+    let mut maze = Maze::new(3, 3, 1);
+    for dir in [
+      Direction::East,
+      Direction::West,
+      Direction::South,
+      Direction::North,
+    ] {
+      maze.carve([1, 1, 0], dir);
+    }
+    assert!(maze.add_crossing([1, 1, 0]));
+    maze
+  }
+
+  #[test]
+  fn paths_cannot_turn_at_a_crossing() {
+    let mut maze = plus();
+    let straight = solver::bfs(&maze, [0, 1, 0], [2, 1, 0]).unwrap();
+    assert_eq!(straight, vec![[0, 1, 0], [1, 1, 0], [2, 1, 0]]);
+    assert_eq!(
+      solver::bfs(&maze, [0, 1, 0], [1, 0, 0]),
+      Err(SolveError::NoPath {
+        start: [0, 1, 0],
+        end: [1, 0, 0]
+      })
+    );
+    assert!(maze.remove_crossing([1, 1, 0]));
+    assert_eq!(solver::bfs(&maze, [0, 1, 0], [1, 0, 0]).unwrap().len(), 3);
+  }
+
+  #[test]
+  fn crossings_survive_json() {
+    let maze = plus();
+    assert_eq!(
+      Maze::from_json(&maze.to_json()).unwrap().crossings(),
+      vec![[1, 1, 0]]
+    );
+  }
+}
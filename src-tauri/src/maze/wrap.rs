@@ -0,0 +1,116 @@
+//! Toroidal mazes, whose edges join up with the opposite ones.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Coord, Direction, Maze};
+
+/// Which pairs of opposite grid edges border each other.
+///
+/// With `WrapX` the east face of the last column opens onto the west face of the first, and
+/// likewise along `y` for `WrapY`. Levels never wrap. An axis shorter than three cells does
+/// not wrap either, since its cells would border the same neighbour twice or themselves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapMode {
+  #[default]
+  None,
+  WrapX,
+  WrapY,
+  WrapXY,
+}
+
+impl WrapMode {
+  pub fn is_none(&self) -> bool {
+    *self == WrapMode::None
+  }
+
+  /// Whether `axis` (`0` for `x`, `1` for `y`) wraps, ignoring its length.
+  pub const fn wraps(self, axis: usize) -> bool {
+    matches!(
+      (self, axis),
+      (WrapMode::WrapX | WrapMode::WrapXY, 0) | (WrapMode::WrapY | WrapMode::WrapXY, 1)
+    )
+  }
+}
+
+impl Maze {
+  pub fn wrap(&self) -> WrapMode {
+    self.wrap
+  }
+
+  /// Whether `axis` actually wraps, being both selected and at least three cells long.
+  pub fn wraps(&self, axis: usize) -> bool {
+    let limits = [self.width, self.height, self.depth];
+    self.wrap.wraps(axis) && limits[axis] > 2
+  }
+
+  /// Switches to `wrap`. Faces that stop joining two cells are walled, and locks on them are
+  /// dropped, so the maze stays valid; faces that start joining two cells stay walled.
+  pub fn set_wrap(&mut self, wrap: WrapMode) {
+    for index in 0..self.cell_count() {
+      let cell = self.coord(index);
+      for dir in Direction::ALL {
+        if self.step(cell, dir, self.wrap) != self.step(cell, dir, wrap) {
+          self.walls[index] |= dir.bit();
+        }
+      }
+    }
+    self.wrap = wrap;
+    let locks = std::mem::take(&mut self.locks);
+    self.locks = locks
+      .into_iter()
+      .filter(|lock| self.neighbor(lock.door, lock.dir).is_some())
+      .collect();
+  }
+
+  /// The grid cell on the other side of `dir` under `wrap`, ignoring the mask.
+  pub(crate) fn step(&self, [x, y, z]: Coord, dir: Direction, wrap: WrapMode) -> Option<Coord> {
+    let [dx, dy, dz] = dir.offset();
+    let next = [x as i64 + dx, y as i64 + dy, z as i64 + dz];
+    let limits = [self.width, self.height, self.depth].map(i64::from);
+    let mut cell = [0; 3];
+    for axis in 0..3 {
+      let (v, max) = (next[axis], limits[axis]);
+      cell[axis] = if (0..max).contains(&v) {
+        v as u32
+      } else if wrap.wraps(axis) && max > 2 {
+        v.rem_euclid(max) as u32
+      } else {
+        return None;
+      };
+    }
+    Some(cell)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver;
+
+  #[test]
+  fn wrapping_shortens_the_way_round() {
+    let mut maze = Maze::new(6, 1, 1);
+    maze.set_wrap(WrapMode::WrapX);
+    for x in 0..6 {
+      assert!(maze.carve([x, 0, 0], Direction::East).is_some());
+    }
+    assert_eq!(
+      solver::bfs(&maze, [0, 0, 0], [5, 0, 0]).unwrap(),
+      vec![[0, 0, 0], [5, 0, 0]]
+    );
+
+    maze.set_wrap(WrapMode::None);
+    assert!(maze.has_wall([5, 0, 0], Direction::East));
+    assert_eq!(solver::bfs(&maze, [0, 0, 0], [5, 0, 0]).unwrap().len(), 6);
+  }
+
+  #[test]
+  fn short_axes_do_not_wrap() {
+    let mut maze = Maze::new(2, 3, 1);
+    maze.set_wrap(WrapMode::WrapXY);
+    assert!(!maze.wraps(0) && maze.wraps(1));
+    assert_eq!(maze.neighbor([1, 0, 0], Direction::East), None);
+    assert_eq!(maze.neighbor([0, 0, 0], Direction::North), Some([0, 2, 0]));
+  }
+}
@@ -0,0 +1,536 @@
+//! Path finding over carved mazes.
+//!
+//! Searches follow open faces in all six directions by default, so a path may climb or descend
+//! between levels wherever the generator left a vertical passage. The `_in` variants only move
+//! through the faces a [`Topology`] allows. Weave crossings only let a path straight through,
+//! and on a maze that [`Maze::wrap`]s a path may leave one edge and come back in at the other.
+//! [`SolverIter`] runs the breadth-first search step by step for animation, and
+//! [`solve_with_keys`] honours the maze's locked doors.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::maze::{Coord, Direction, Maze, Orthogonal6, Topology};
+
+mod keys;
+mod steps;
+
+pub use keys::{solve_with_keys, solve_with_keys_in, MAX_LOCKS};
+pub use steps::{SolverIter, SolverStep};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+  /// A start or end coordinate lies outside the maze.
+  OutOfBounds(Coord),
+  /// No sequence of open faces links the two cells.
+  NoPath { start: Coord, end: Coord },
+  /// The maze has more locks than [`MAX_LOCKS`], so a key search cannot track them.
+  TooManyLocks(usize),
+}
+
+impl fmt::Display for SolveError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SolveError::OutOfBounds([x, y, z]) => write!(f, "cell ({x}, {y}, {z}) is outside the maze"),
+      SolveError::NoPath { start, end } => write!(f, "no path from {start:?} to {end:?}"),
+      SolveError::TooManyLocks(count) => write!(
+        f,
+        "maze has {count} locks but a key search tracks at most {MAX_LOCKS}"
+      ),
+    }
+  }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Shortest path from `start` to `end`, both included, found by breadth-first search.
+pub fn bfs(maze: &Maze, start: Coord, end: Coord) -> Result<Vec<Coord>, SolveError> {
+  bfs_in(maze, start, end, &Orthogonal6)
+}
+
+/// [`bfs`] moving only through the faces `topology` allows.
+pub fn bfs_in(
+  maze: &Maze,
+  start: Coord,
+  end: Coord,
+  topology: &dyn Topology,
+) -> Result<Vec<Coord>, SolveError> {
+  explore_in(maze, start, end, topology)?
+    .path
+    .ok_or(SolveError::NoPath { start, end })
+}
+
+/// What a breadth-first search found, for showing how far it got.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolveResult {
+  /// Shortest path from start to end, both included, or `None` if the end is unreachable.
+  pub path: Option<Vec<Coord>>,
+  /// Every cell the search reached, in discovery order, starting with the start. Without a
+  /// path this is the whole region around the start.
+  pub visited: Vec<Coord>,
+}
+
+/// [`bfs`] that also reports the cells it reached, and an unreachable end as a missing path
+/// rather than an error.
+pub fn explore(maze: &Maze, start: Coord, end: Coord) -> Result<SolveResult, SolveError> {
+  explore_in(maze, start, end, &Orthogonal6)
+}
+
+/// [`explore`] moving only through the faces `topology` allows.
+pub fn explore_in(
+  maze: &Maze,
+  start: Coord,
+  end: Coord,
+  topology: &dyn Topology,
+) -> Result<SolveResult, SolveError> {
+  check_bounds(maze, start)?;
+  check_bounds(maze, end)?;
+
+  let graph = Graph::new(maze, topology);
+  let mut parent = vec![usize::MAX; graph.len()];
+  let mut queue = VecDeque::new();
+  for node in graph.nodes(start) {
+    parent[node] = node;
+    queue.push_back(node);
+  }
+  // A crossing is one cell however many of its corridors the search enters.
+  let mut seen = vec![false; maze.cell_count()];
+  seen[maze.index(start)] = true;
+  let mut visited = vec![start];
+
+  while let Some(node) = queue.pop_front() {
+    if graph.cell(node) == end {
+      return Ok(SolveResult {
+        path: Some(trace_back(&graph, &parent, node)),
+        visited,
+      });
+    }
+    for next in graph.successors(node) {
+      if parent[next] == usize::MAX {
+        parent[next] = node;
+        queue.push_back(next);
+        let cell = graph.cell(next);
+        if !std::mem::replace(&mut seen[maze.index(cell)], true) {
+          visited.push(cell);
+        }
+      }
+    }
+  }
+  Ok(SolveResult {
+    path: None,
+    visited,
+  })
+}
+
+/// Distance estimate used to order the A* frontier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heuristic {
+  /// `|dx| + |dy| + |dz|`; exact whenever there are no walls in the way.
+  Manhattan3D,
+  /// Straight-line distance across all three axes.
+  Euclidean,
+  /// No estimate at all, which turns A* into a uniform-cost search.
+  Zero,
+}
+
+impl Heuristic {
+  pub fn estimate(self, from: Coord, to: Coord) -> f64 {
+    self.combine([0, 1, 2].map(|axis| from[axis].abs_diff(to[axis]) as f64))
+  }
+
+  /// [`Heuristic::estimate`] taking the short way round every axis `maze` wraps, so it stays
+  /// admissible on a torus.
+  pub fn estimate_in(self, maze: &Maze, from: Coord, to: Coord) -> f64 {
+    let limits = [maze.width(), maze.height(), maze.depth()];
+    self.combine([0, 1, 2].map(|axis| {
+      let delta = from[axis].abs_diff(to[axis]);
+      let delta = if maze.wraps(axis) {
+        delta.min(limits[axis] - delta)
+      } else {
+        delta
+      };
+      delta as f64
+    }))
+  }
+
+  fn combine(self, delta: [f64; 3]) -> f64 {
+    match self {
+      Heuristic::Manhattan3D => delta.iter().sum(),
+      Heuristic::Euclidean => delta.iter().map(|d| d * d).sum::<f64>().sqrt(),
+      Heuristic::Zero => 0.0,
+    }
+  }
+}
+
+/// Path found by [`astar`] together with the search effort it took.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+  pub path: Vec<Coord>,
+  /// Cells taken off the frontier and expanded.
+  pub expanded: usize,
+}
+
+/// Shortest path from `start` to `end` by A* search guided by `heuristic`.
+///
+/// Every heuristic is admissible on the unit-cost grid, so the path is as short as the one
+/// from [`bfs`]; better estimates only reduce [`SearchResult::expanded`].
+pub fn astar(
+  maze: &Maze,
+  start: Coord,
+  end: Coord,
+  heuristic: Heuristic,
+) -> Result<SearchResult, SolveError> {
+  astar_in(maze, start, end, heuristic, &Orthogonal6)
+}
+
+/// [`astar`] moving only through the faces `topology` allows.
+pub fn astar_in(
+  maze: &Maze,
+  start: Coord,
+  end: Coord,
+  heuristic: Heuristic,
+  topology: &dyn Topology,
+) -> Result<SearchResult, SolveError> {
+  check_bounds(maze, start)?;
+  check_bounds(maze, end)?;
+
+  let graph = Graph::new(maze, topology);
+  let mut cost = vec![u32::MAX; graph.len()];
+  let mut parent = vec![usize::MAX; graph.len()];
+  let mut closed = vec![false; graph.len()];
+  let mut open = BinaryHeap::new();
+  for node in graph.nodes(start) {
+    cost[node] = 0;
+    parent[node] = node;
+    open.push(Frontier {
+      estimate: heuristic.estimate_in(maze, start, end),
+      cost: 0,
+      index: node,
+    });
+  }
+  let mut expanded = 0;
+
+  while let Some(Frontier { index, .. }) = open.pop() {
+    if closed[index] {
+      continue;
+    }
+    closed[index] = true;
+    expanded += 1;
+    if graph.cell(index) == end {
+      return Ok(SearchResult {
+        path: trace_back(&graph, &parent, index),
+        expanded,
+      });
+    }
+
+    let next_cost = cost[index] + 1;
+    for next in graph.successors(index) {
+      if next_cost < cost[next] {
+        cost[next] = next_cost;
+        parent[next] = index;
+        open.push(Frontier {
+          estimate: next_cost as f64 + heuristic.estimate_in(maze, graph.cell(next), end),
+          cost: next_cost,
+          index: next,
+        });
+      }
+    }
+  }
+  Err(SolveError::NoPath { start, end })
+}
+
+/// A* frontier entry for a [`Graph`] node, ordered so the max-heap pops the lowest estimate
+/// first and prefers the deeper entry on ties.
+struct Frontier {
+  estimate: f64,
+  cost: u32,
+  index: usize,
+}
+
+impl Ord for Frontier {
+  fn cmp(&self, other: &Self) -> Ordering {
+    other
+      .estimate
+      .total_cmp(&self.estimate)
+      .then(self.cost.cmp(&other.cost))
+  }
+}
+
+impl PartialOrd for Frontier {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for Frontier {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Frontier {}
+
+/// Breadth-first distance from `source` to every cell over all six directions, in
+/// [`Maze::index`] order, `u32::MAX` where unreachable.
+///
+/// A `source` outside the maze or masked off reaches nothing, itself included.
+pub fn distance_field(maze: &Maze, source: Coord) -> Vec<u32> {
+  distance_field_in(maze, source, &Orthogonal6)
+}
+
+/// [`distance_field`] moving only through the faces `topology` allows.
+pub fn distance_field_in(maze: &Maze, source: Coord, topology: &dyn Topology) -> Vec<u32> {
+  if !maze.is_active(source) {
+    return vec![u32::MAX; maze.cell_count()];
+  }
+  let graph = Graph::new(maze, topology);
+  let mut distance = vec![u32::MAX; graph.len()];
+  let mut queue = VecDeque::new();
+  for node in graph.nodes(source) {
+    distance[node] = 0;
+    queue.push_back(node);
+  }
+  while let Some(node) = queue.pop_front() {
+    let next_distance = distance[node] + 1;
+    for next in graph.successors(node) {
+      if distance[next] == u32::MAX {
+        distance[next] = next_distance;
+        queue.push_back(next);
+      }
+    }
+  }
+  // Fold the second corridor of every crossing into its cell.
+  let cells = maze.cell_count();
+  for (offset, &index) in graph.extra.iter().enumerate() {
+    distance[index] = distance[index].min(distance[cells + offset]);
+  }
+  distance.truncate(cells);
+  distance
+}
+
+/// The reachable cell with the largest distance, preferring the lowest index on ties.
+pub(crate) fn farthest(maze: &Maze, distances: &[u32]) -> Coord {
+  let (index, _) = distances
+    .iter()
+    .enumerate()
+    .filter(|&(_, &d)| d != u32::MAX)
+    .fold(
+      (0, 0),
+      |best, (index, &d)| if d > best.1 { (index, d) } else { best },
+    );
+  maze.coord(index)
+}
+
+fn check_bounds(maze: &Maze, cell: Coord) -> Result<(), SolveError> {
+  if maze.contains(cell) {
+    Ok(())
+  } else {
+    Err(SolveError::OutOfBounds(cell))
+  }
+}
+
+/// Walks `parent` links from `end` back to a start node and returns the path in forward order.
+fn trace_back(graph: &Graph, parent: &[usize], end: usize) -> Vec<Coord> {
+  let mut path = vec![graph.cell(end)];
+  let mut node = end;
+  while parent[node] != node {
+    node = parent[node];
+    path.push(graph.cell(node));
+  }
+  path.reverse();
+  path
+}
+
+/// What the searches walk: one node per cell, numbered like [`Maze::index`], plus a second
+/// node per weave crossing so its two corridors never meet. A crossing's own index stands for
+/// its east-west corridor and the extra node for the north-south one.
+struct Graph<'a> {
+  maze: &'a Maze,
+  topology: &'a dyn Topology,
+  /// North-south node of each crossing, keyed by cell index.
+  crossings: HashMap<usize, usize>,
+  /// Cell index of each extra node, in node order after the cells.
+  extra: Vec<usize>,
+}
+
+impl<'a> Graph<'a> {
+  fn new(maze: &'a Maze, topology: &'a dyn Topology) -> Graph<'a> {
+    let extra: Vec<usize> = maze
+      .crossings()
+      .into_iter()
+      .map(|cell| maze.index(cell))
+      .collect();
+    let crossings = extra
+      .iter()
+      .enumerate()
+      .map(|(offset, &index)| (index, maze.cell_count() + offset))
+      .collect();
+    Graph {
+      maze,
+      topology,
+      crossings,
+      extra,
+    }
+  }
+
+  fn len(&self) -> usize {
+    self.maze.cell_count() + self.extra.len()
+  }
+
+  fn cell(&self, node: usize) -> Coord {
+    let cells = self.maze.cell_count();
+    self.maze.coord(if node < cells {
+      node
+    } else {
+      self.extra[node - cells]
+    })
+  }
+
+  /// Nodes a search from `cell` starts on: both corridors when it is a crossing.
+  fn nodes(&self, cell: Coord) -> impl Iterator<Item = usize> {
+    let index = self.maze.index(cell);
+    std::iter::once(index).chain(self.crossings.get(&index).copied())
+  }
+
+  /// Node reached by stepping `dir` into `cell`; a crossing can only be entered along one of
+  /// its corridors.
+  fn enter(&self, cell: Coord, dir: Direction) -> Option<usize> {
+    let index = self.maze.index(cell);
+    match (self.crossings.get(&index), dir) {
+      (None, _) | (Some(_), Direction::East | Direction::West) => Some(index),
+      (Some(&north_south), Direction::South | Direction::North) => Some(north_south),
+      (Some(_), Direction::Up | Direction::Down) => None,
+    }
+  }
+
+  fn successors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+    self.moves(node).map(|(_, next)| next)
+  }
+
+  /// [`Graph::successors`] together with the face each one is reached through.
+  fn moves(&self, node: usize) -> impl Iterator<Item = (Direction, usize)> + '_ {
+    let cell = self.cell(node);
+    // Inside a crossing the only way on is along the corridor the node stands for.
+    let corridor = self
+      .crossings
+      .contains_key(&self.maze.index(cell))
+      .then(|| {
+        if node < self.maze.cell_count() {
+          [Direction::East, Direction::West]
+        } else {
+          [Direction::South, Direction::North]
+        }
+      });
+    self
+      .maze
+      .open_moves(self.topology, cell)
+      .filter(move |(dir, _)| corridor.is_none_or(|corridor| corridor.contains(dir)))
+      .filter_map(|(dir, next)| Some((dir, self.enter(next, dir)?)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+
+  /// Whether each step of `path` moves to a face neighbour.
+  fn is_walk(path: &[Coord]) -> bool {
+    path.windows(2).all(|pair| {
+      let moved: u32 = (0..3)
+        .map(|axis| pair[0][axis].abs_diff(pair[1][axis]))
+        .sum();
+      moved == 1
+    })
+  }
+
+  #[test]
+  fn solves_a_generated_maze() {
+    let maze = Maze::generate_with_seed((6, 6, 3), 5, Algorithm::Prim).unwrap();
+    let path = bfs(&maze, [0, 0, 0], [5, 5, 2]).unwrap();
+    assert_eq!(path.first(), Some(&[0, 0, 0]));
+    assert_eq!(path.last(), Some(&[5, 5, 2]));
+    assert!(is_walk(&path));
+  }
+
+  #[test]
+  fn reports_an_unsolvable_maze() {
+    let maze = Maze::new(4, 4, 1);
+    assert_eq!(
+      bfs(&maze, [0, 0, 0], [3, 3, 0]),
+      Err(SolveError::NoPath {
+        start: [0, 0, 0],
+        end: [3, 3, 0]
+      })
+    );
+  }
+
+  #[test]
+  fn rejects_a_start_outside_the_maze() {
+    let maze = Maze::generate_with_seed((4, 4, 1), 1, Algorithm::Kruskal).unwrap();
+    assert_eq!(
+      bfs(&maze, [4, 0, 0], [3, 3, 0]),
+      Err(SolveError::OutOfBounds([4, 0, 0]))
+    );
+  }
+
+  #[test]
+  fn manhattan_expands_fewer_cells_than_no_estimate() {
+    let maze = Maze::generate_with_seed((30, 30, 5), 0, Algorithm::Kruskal).unwrap();
+    let (start, end) = ([0, 0, 0], [29, 29, 4]);
+    let guided = astar(&maze, start, end, Heuristic::Manhattan3D).unwrap();
+    let blind = astar(&maze, start, end, Heuristic::Zero).unwrap();
+    assert_eq!(guided.path.len(), blind.path.len());
+    assert_eq!(guided.path.len(), bfs(&maze, start, end).unwrap().len());
+    assert!(
+      guided.expanded < blind.expanded,
+      "{} vs {}",
+      guided.expanded,
+      blind.expanded
+    );
+  }
+
+  #[test]
+  fn distance_field_counts_moves_and_marks_walled_off_cells() {
+    // A corridor round the top row and down, with the two cells below it sealed.
+    let mut maze = Maze::new(3, 2, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([1, 0, 0], Direction::East);
+    maze.carve([2, 0, 0], Direction::South);
+    assert_eq!(
+      distance_field(&maze, [0, 0, 0]),
+      vec![0, 1, 2, u32::MAX, u32::MAX, 3]
+    );
+    assert_eq!(distance_field(&maze, [5, 0, 0]), vec![u32::MAX; 6]);
+  }
+
+  #[test]
+  fn explore_visits_exactly_the_start_component() {
+    let mut maze = Maze::new(4, 3, 1);
+    for y in 0..3 {
+      maze.carve([0, y, 0], Direction::East);
+    }
+    for y in 0..2 {
+      maze.carve([0, y, 0], Direction::South);
+      maze.carve([2, y, 0], Direction::South);
+    }
+    maze.carve([2, 0, 0], Direction::East);
+
+    let left = explore(&maze, [0, 0, 0], [3, 2, 0]).unwrap();
+    assert_eq!(left.path, None);
+    assert_eq!(left.visited.len(), 6);
+    assert!(left.visited.iter().all(|cell| cell[0] < 2));
+
+    let right = explore(&maze, [3, 0, 0], [3, 2, 0]).unwrap();
+    assert_eq!(right.path, None);
+    assert_eq!(right.visited.len(), 4);
+    let mut unique = right.visited.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), 4);
+  }
+}
@@ -0,0 +1,147 @@
+//! Searching a maze whose locked doors need their keys first.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use super::{check_bounds, Graph, SolveError};
+use crate::maze::{Coord, Direction, Maze, Orthogonal6, Topology};
+
+/// Most locks [`solve_with_keys`] handles; the keys held are tracked as bits of a `u64`.
+pub const MAX_LOCKS: usize = 64;
+
+/// Shortest path from `start` to `end`, both included, that only passes each locked door after
+/// visiting its key cell. Keys are never used up, and a key on `start` is held from the outset.
+pub fn solve_with_keys(maze: &Maze, start: Coord, end: Coord) -> Result<Vec<Coord>, SolveError> {
+  solve_with_keys_in(maze, start, end, &Orthogonal6)
+}
+
+/// [`solve_with_keys`] moving only through the faces `topology` allows.
+///
+/// The search runs over pairs of a position and the set of keys held, so it may visit a cell
+/// once for every set of keys that can reach it.
+pub fn solve_with_keys_in(
+  maze: &Maze,
+  start: Coord,
+  end: Coord,
+  topology: &dyn Topology,
+) -> Result<Vec<Coord>, SolveError> {
+  check_bounds(maze, start)?;
+  check_bounds(maze, end)?;
+  let locks = maze.locks();
+  if locks.len() > MAX_LOCKS {
+    return Err(SolveError::TooManyLocks(locks.len()));
+  }
+
+  // Keys picked up in each cell, and the keys each side of every door asks for.
+  let mut keys: HashMap<Coord, u64> = HashMap::new();
+  let mut doors: HashMap<(Coord, Direction), u64> = HashMap::new();
+  for (bit, lock) in locks.iter().enumerate() {
+    *keys.entry(lock.key).or_default() |= 1 << bit;
+    *doors.entry((lock.door, lock.dir)).or_default() |= 1 << bit;
+    if let Some(next) = maze.neighbor(lock.door, lock.dir) {
+      *doors.entry((next, lock.dir.opposite())).or_default() |= 1 << bit;
+    }
+  }
+  let pick_up = |cell: Coord, held: u64| held | keys.get(&cell).copied().unwrap_or(0);
+
+  let graph = Graph::new(maze, topology);
+  // Every state reached as `(node, held)`, with the state it was reached from.
+  let mut states: Vec<(usize, u64)> = Vec::new();
+  let mut parent: Vec<usize> = Vec::new();
+  let mut seen: HashMap<(usize, u64), usize> = HashMap::new();
+  let mut queue = VecDeque::new();
+  for node in graph.nodes(start) {
+    let state = (node, pick_up(start, 0));
+    seen.insert(state, states.len());
+    queue.push_back(states.len());
+    parent.push(states.len());
+    states.push(state);
+  }
+
+  while let Some(id) = queue.pop_front() {
+    let (node, held) = states[id];
+    let cell = graph.cell(node);
+    if cell == end {
+      let mut path = vec![cell];
+      let mut id = id;
+      while parent[id] != id {
+        id = parent[id];
+        path.push(graph.cell(states[id].0));
+      }
+      path.reverse();
+      return Ok(path);
+    }
+    for (dir, next) in graph.moves(node) {
+      let needed = doors.get(&(cell, dir)).copied().unwrap_or(0);
+      if needed & !held != 0 {
+        continue;
+      }
+      let state = (next, pick_up(graph.cell(next), held));
+      if let Entry::Vacant(entry) = seen.entry(state) {
+        entry.insert(states.len());
+        queue.push_back(states.len());
+        parent.push(id);
+        states.push(state);
+      }
+    }
+  }
+  Err(SolveError::NoPath { start, end })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::{Algorithm, GenConfig, LockConfig};
+  use crate::maze::Lock;
+  use crate::solver;
+
+  #[test]
+  fn collects_every_key_before_its_door() {
+    let config = GenConfig {
+      locks: Some(LockConfig { keys: 3 }),
+      ..GenConfig::new((8, 8, 2), Algorithm::RecursiveBacktracker, 5)
+    };
+    let maze = Maze::generate(&config).unwrap();
+    assert_eq!(maze.locks().len(), 3);
+    let (start, end) = (maze.entrance(), maze.exit());
+    assert!(solver::bfs(&maze, start, end).is_ok());
+
+    let path = solve_with_keys(&maze, start, end).unwrap();
+    assert_eq!((path[0], path[path.len() - 1]), (start, end));
+    for lock in maze.locks() {
+      let far = maze.neighbor(lock.door, lock.dir).unwrap();
+      let through = path
+        .windows(2)
+        .position(|pair| {
+          (pair[0], pair[1]) == (lock.door, far) || (pair[0], pair[1]) == (far, lock.door)
+        })
+        .expect("a perfect maze has no way round a door");
+      assert!(path[..=through].contains(&lock.key), "{lock:?}");
+    }
+  }
+
+  #[test]
+  fn a_key_behind_its_own_door_is_unreachable() {
+    let mut maze = Maze::new(3, 1, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    maze.carve([1, 0, 0], Direction::East);
+    assert!(maze.add_lock(Lock {
+      key: [2, 0, 0],
+      door: [0, 0, 0],
+      dir: Direction::East,
+    }));
+    assert!(solver::bfs(&maze, [0, 0, 0], [2, 0, 0]).is_ok());
+    assert_eq!(
+      solve_with_keys(&maze, [0, 0, 0], [2, 0, 0]),
+      Err(SolveError::NoPath {
+        start: [0, 0, 0],
+        end: [2, 0, 0]
+      })
+    );
+    // Starting on the key works from the other side.
+    assert_eq!(
+      solve_with_keys(&maze, [2, 0, 0], [0, 0, 0]).unwrap().len(),
+      3
+    );
+  }
+}
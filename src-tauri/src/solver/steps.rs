@@ -0,0 +1,153 @@
+//! Breadth-first search one layer at a time, for animating it.
+
+use serde::Serialize;
+
+use super::{check_bounds, trace_back, Graph, SolveError};
+use crate::maze::{Coord, Maze, Topology};
+
+/// One frame of a [`SolverIter`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolverStep {
+  /// Cells reached for the first time, one move further from the start than the previous
+  /// step's; the first step holds only the start.
+  pub visited: Vec<Coord>,
+  /// Whether this step reached the end. It is the last step if so.
+  pub found: bool,
+  /// The path [`super::explore`] would return, on the step that reaches the end.
+  pub path: Option<Vec<Coord>>,
+}
+
+/// The search of [`super::explore_in`] split into [`SolverStep`]s.
+///
+/// No randomness is involved, so the same maze always yields the same steps. Without a path
+/// the iterator simply ends once the start's whole region has been visited.
+pub struct SolverIter<'a> {
+  graph: Graph<'a>,
+  start: Coord,
+  end: Coord,
+  parent: Vec<usize>,
+  seen: Vec<bool>,
+  /// Nodes reached by the last step, which the next one expands; `None` before the first.
+  layer: Option<Vec<usize>>,
+  finished: bool,
+}
+
+impl<'a> SolverIter<'a> {
+  pub fn new(
+    maze: &'a Maze,
+    start: Coord,
+    end: Coord,
+    topology: &'a dyn Topology,
+  ) -> Result<SolverIter<'a>, SolveError> {
+    check_bounds(maze, start)?;
+    check_bounds(maze, end)?;
+    let graph = Graph::new(maze, topology);
+    Ok(SolverIter {
+      parent: vec![usize::MAX; graph.len()],
+      seen: vec![false; maze.cell_count()],
+      graph,
+      start,
+      end,
+      layer: None,
+      finished: false,
+    })
+  }
+}
+
+impl Iterator for SolverIter<'_> {
+  type Item = SolverStep;
+
+  fn next(&mut self) -> Option<SolverStep> {
+    if self.finished {
+      return None;
+    }
+    let mut reached = Vec::new();
+    match &self.layer {
+      None => {
+        for node in self.graph.nodes(self.start) {
+          self.parent[node] = node;
+          reached.push(node);
+        }
+      }
+      // Same order as the queue of `explore_in`, so every node gets the same parent.
+      Some(layer) => {
+        'expand: for &node in layer {
+          for next in self.graph.successors(node) {
+            if self.parent[next] == usize::MAX {
+              self.parent[next] = node;
+              reached.push(next);
+              if self.graph.cell(next) == self.end {
+                break 'expand;
+              }
+            }
+          }
+        }
+      }
+    }
+    if reached.is_empty() {
+      self.finished = true;
+      return None;
+    }
+
+    let maze = self.graph.maze;
+    let mut visited = Vec::new();
+    let mut path = None;
+    for &node in &reached {
+      let cell = self.graph.cell(node);
+      if !std::mem::replace(&mut self.seen[maze.index(cell)], true) {
+        visited.push(cell);
+      }
+      if path.is_none() && cell == self.end {
+        path = Some(trace_back(&self.graph, &self.parent, node));
+      }
+    }
+    self.finished = path.is_some();
+    self.layer = Some(reached);
+    Some(SolverStep {
+      visited,
+      found: path.is_some(),
+      path,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::generator::Algorithm;
+  use crate::maze::{Direction, Orthogonal6};
+  use crate::solver;
+
+  #[test]
+  fn steps_rebuild_the_shortest_path() {
+    let maze = Maze::generate_with_seed((7, 7, 2), 11, Algorithm::Prim).unwrap();
+    let (start, end) = ([0, 0, 0], [6, 6, 1]);
+    let steps: Vec<SolverStep> = SolverIter::new(&maze, start, end, &Orthogonal6)
+      .unwrap()
+      .collect();
+    let path = solver::bfs(&maze, start, end).unwrap();
+    assert_eq!(steps.len(), path.len());
+    for (step, cell) in steps.iter().zip(&path) {
+      assert!(step.visited.contains(cell), "{cell:?}");
+    }
+    let last = steps.last().unwrap();
+    assert!(last.found);
+    assert_eq!(last.path.as_ref(), Some(&path));
+    assert!(steps[..steps.len() - 1]
+      .iter()
+      .all(|step| !step.found && step.path.is_none()));
+  }
+
+  #[test]
+  fn steps_end_after_the_region_without_a_path() {
+    let mut maze = Maze::new(3, 1, 1);
+    maze.carve([0, 0, 0], Direction::East);
+    let steps: Vec<SolverStep> = SolverIter::new(&maze, [0, 0, 0], [2, 0, 0], &Orthogonal6)
+      .unwrap()
+      .collect();
+    let visited: Vec<Vec<Coord>> = steps.iter().map(|step| step.visited.clone()).collect();
+    assert_eq!(visited, [vec![[0, 0, 0]], vec![[1, 0, 0]]]);
+    assert!(steps.iter().all(|step| !step.found));
+  }
+}